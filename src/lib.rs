@@ -1,6 +1,11 @@
 use cfg_if::cfg_if;
 use std::collections::HashMap;
-use uc3m_timetable::{Timetable, TimetableId, UC3M_TIMEZONE};
+use uc3m_timetable::holidays::HolidayCalendar;
+use uc3m_timetable::ical::{is_color_name, Calendar, Chunks};
+use uc3m_timetable::{Duration, Timetable, TimetableId, TimetableOptions, UC3M_TIMEZONE};
+use worker::js_sys::{self, Array, Function, Promise, Reflect, Uint8Array};
+use worker::wasm_bindgen::{JsCast, JsValue};
+use worker::wasm_bindgen_futures::{spawn_local, JsFuture};
 use worker::*;
 
 macro_rules! parse_query_param {
@@ -25,8 +30,9 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         .get_async("/", |req, _ctx| async move {
             let url = req.url()?;
             let query_params: HashMap<_, _> = url.query_pairs().into_iter().collect();
-            let id = TimetableId::new(
-                parse_query_param!(&query_params, "year"),
+            let year = parse_query_param!(&query_params, "year");
+            let mut id = TimetableId::new(
+                year,
                 parse_query_param!(&query_params, "plan"),
                 parse_query_param!(&query_params, "center"),
                 parse_query_param!(&query_params, "grade"),
@@ -34,13 +40,79 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
                 parse_query_param!(&query_params, "period"),
                 UC3M_TIMEZONE,
             );
+            if let Some(language) = query_params.get("lang") {
+                match language.parse() {
+                    Ok(language) => id = id.language(language),
+                    Err(_) => return Response::error("invalid `lang` query parameter", 400),
+                }
+            }
 
-            match Timetable::fetch(id).await {
+            // Serve the well-formed sessions even if some cells cannot be parsed.
+            let mut options = TimetableOptions::new()
+                .lenient(true)
+                .source(url.as_str())
+                .refresh_interval(Duration::days(1))
+                // The worker keeps no state, so unchanged timetables
+                // keep their DTSTAMP by deriving it from the contents.
+                .content_stamp(true);
+            let mut holidays = None;
+            if query_params
+                .get("public_holidays")
+                .map_or(false, |flag| flag == "true")
+            {
+                match HolidayCalendar::public_holidays(year) {
+                    Some(public_holidays) => holidays = Some(public_holidays),
+                    None => {
+                        return Response::error(
+                            format!("no public holiday data for the {} academic year", year),
+                            400,
+                        )
+                    }
+                }
+            }
+            // The university non-teaching days aren't bundled, so they are
+            // given as comma-separated `YYYY-MM-DD` dates.
+            if let Some(days) = query_params.get("non_teaching_days") {
+                match HolidayCalendar::from_csv(&days.replace(',', "\n")) {
+                    Ok(days) => holidays
+                        .get_or_insert_with(HolidayCalendar::new)
+                        .extend(days.dates()),
+                    Err(_) => {
+                        return Response::error("invalid `non_teaching_days` query parameter", 400)
+                    }
+                }
+            }
+            if let Some(holidays) = holidays {
+                options = options.holidays(holidays);
+            }
+            if let Some(color) = query_params.get("color") {
+                if !is_color_name(color) {
+                    return Response::error("invalid `color` query parameter", 400);
+                }
+                options = options.color(color.as_ref());
+            }
+            // Comma-separated reminder offsets, in minutes before each session.
+            if let Some(reminders) = query_params.get("reminders") {
+                for minutes in reminders.split(',') {
+                    match minutes.trim().parse::<u32>() {
+                        Ok(minutes) => {
+                            options = options.reminder(Duration::minutes(minutes.into()))
+                        }
+                        Err(_) => {
+                            return Response::error("invalid `reminders` query parameter", 400)
+                        }
+                    }
+                }
+            }
+            match Timetable::fetch_with_options(id, &options).await {
                 Ok(timetable) => {
+                    for diagnostic in timetable.diagnostics() {
+                        console_log!("{}", diagnostic);
+                    }
                     let mut headers = Headers::new();
                     headers.set("Content-Type", "text/calendar")?;
                     headers.set("Cache-Control", "public, max-age=3600")?;
-                    Ok(Response::ok(timetable.calendar().to_string())?.with_headers(headers))
+                    Ok(stream_calendar(timetable.into_calendar())?.with_headers(headers))
                 }
                 Err(err) => Response::error(format!("cannot parse timetable: {}", err), 500),
             }
@@ -49,6 +121,46 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         .await
 }
 
+/// Creates a response whose body is written while the chunks
+/// of `calendar` are serialized.
+fn stream_calendar(calendar: Calendar) -> Result<Response> {
+    let stream = Reflect::construct(&global_class("TransformStream")?, &Array::new())?;
+    let writable = Reflect::get(&stream, &"writable".into())?;
+    let writer = call_method(&writable, "getWriter", &Array::new())?;
+    spawn_local(async move {
+        if let Err(err) = write_chunks(&writer, calendar.into_chunks()).await {
+            console_error!("cannot stream calendar: {}", err);
+        }
+    });
+    let readable = Reflect::get(&stream, &"readable".into())?;
+    let response = Reflect::construct(&global_class("Response")?, &Array::of1(&readable))?;
+    Response::from_body(ResponseBody::Stream(response.unchecked_into()))
+}
+
+/// Writes each chunk into the `WritableStreamDefaultWriter` once the
+/// previous chunk is consumed, and closes the stream.
+async fn write_chunks(writer: &JsValue, chunks: Chunks<Calendar>) -> Result<()> {
+    for chunk in chunks {
+        let chunk = Uint8Array::from(chunk.as_bytes());
+        let written: Promise = call_method(writer, "write", &Array::of1(&chunk))?.dyn_into()?;
+        JsFuture::from(written).await?;
+    }
+    let closed: Promise = call_method(writer, "close", &Array::new())?.dyn_into()?;
+    JsFuture::from(closed).await?;
+    Ok(())
+}
+
+/// Returns the constructor of a JavaScript global class.
+fn global_class(name: &str) -> Result<Function> {
+    Ok(Reflect::get(&js_sys::global(), &name.into())?.dyn_into()?)
+}
+
+/// Calls the method of a JavaScript object with the given arguments.
+fn call_method(target: &JsValue, name: &str, args: &Array) -> Result<JsValue> {
+    let method: Function = Reflect::get(target, &name.into())?.dyn_into()?;
+    Ok(method.apply(target, args)?)
+}
+
 cfg_if! {
     if #[cfg(feature = "console_error_panic_hook")] {
         extern crate console_error_panic_hook;
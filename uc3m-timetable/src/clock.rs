@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use std::fmt::Debug;
+
+/// A source of the current date and time, used to timestamp
+/// the generated calendars.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current date and time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// A [`Clock`] that reads the system time.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A [`Clock`] that always returns the same instant.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// The generation timestamp of a timetable, along with a hash of
+/// the contents it was generated from.
+///
+/// Passing the stamp of a previous timetable to
+/// [`TimetableOptions::previous_stamp`](crate::TimetableOptions::previous_stamp)
+/// keeps the timestamp unchanged while the contents don't change.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ContentStamp {
+    hash: u64,
+    created_on: DateTime<Utc>,
+}
+
+impl ContentStamp {
+    /// Creates a stamp for contents with the given hash.
+    pub const fn new(hash: u64, created_on: DateTime<Utc>) -> Self {
+        Self { hash, created_on }
+    }
+
+    /// Returns the hash of the timetable contents.
+    pub const fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the instant at which the contents were first generated.
+    pub const fn created_on(&self) -> &DateTime<Utc> {
+        &self.created_on
+    }
+}
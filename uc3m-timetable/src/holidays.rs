@@ -0,0 +1,266 @@
+use crate::ical::{Calendar, ParseCalendarError, PropHolder};
+use crate::model::Session;
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// A set of days on which no lectures take place, such as public holidays.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct HolidayCalendar {
+    dates: BTreeSet<NaiveDate>,
+}
+
+impl HolidayCalendar {
+    /// Creates an empty holiday calendar.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the national and Community of Madrid public holidays of the
+    /// academic year starting in `year`, if they are bundled with this crate.
+    ///
+    /// The non-teaching days of the university and the local holidays of each
+    /// campus are not bundled, since they change with every academic year and
+    /// campus. Load them with [`from_csv`](Self::from_csv) or
+    /// [`from_ics`](Self::from_ics), and [extend](Extend) these holidays.
+    pub fn public_holidays(year: i32) -> Option<Self> {
+        let data = match year {
+            2022 => include_str!("../data/holidays/2022.csv"),
+            _ => return None,
+        };
+        Some(Self::from_csv(data).expect("invalid bundled holiday data"))
+    }
+
+    /// Parses a holiday calendar where each line contains a date formatted as
+    /// `YYYY-MM-DD`, optionally followed by a comma and a description. Empty
+    /// lines and lines starting with `#` are ignored.
+    pub fn from_csv(data: &str) -> Result<Self, InvalidHoliday> {
+        let mut calendar = Self::new();
+        for (index, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let date = line.split(',').next().unwrap().trim();
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| InvalidHoliday::new(index + 1, line))?;
+            calendar.insert(date);
+        }
+        Ok(calendar)
+    }
+
+    /// Parses the start date of each `VEVENT` of an iCalendar object
+    /// as a holiday. The events must be wrapped in a `VCALENDAR` component.
+    pub fn from_ics(data: &str) -> Result<Self, InvalidHoliday> {
+        let ics = data
+            .parse::<Calendar>()
+            .map_err(InvalidHoliday::malformed_calendar)?;
+        let mut calendar = Self::new();
+        let events = ics
+            .components()
+            .iter()
+            .filter(|component| component.name() == "VEVENT");
+        for event in events {
+            let Some(start) = event.first_prop("DTSTART") else {
+                continue;
+            };
+            // Both DATE and DATE-TIME values start with the date.
+            let date = start
+                .value()
+                .get(..8)
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+                .ok_or_else(|| InvalidHoliday {
+                    line: None,
+                    text: start.to_string().trim_end().to_string(),
+                    calendar_error: None,
+                })?;
+            calendar.insert(date);
+        }
+        Ok(calendar)
+    }
+
+    /// Adds a holiday to the calendar.
+    pub fn insert(&mut self, date: NaiveDate) {
+        self.dates.insert(date);
+    }
+
+    /// Tests if the given date is a holiday.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.dates.contains(&date)
+    }
+
+    /// Returns the holidays in chronological order.
+    pub fn dates(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.dates.iter().copied()
+    }
+
+    /// Excludes the occurrences of `session` that fall on a holiday.
+    pub fn apply(&self, session: Session) -> Session {
+        session.excluding(self.dates())
+    }
+}
+
+impl Extend<NaiveDate> for HolidayCalendar {
+    fn extend<I: IntoIterator<Item = NaiveDate>>(&mut self, dates: I) {
+        self.dates.extend(dates);
+    }
+}
+
+/// The error returned when a holiday calendar contains a malformed line.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct InvalidHoliday {
+    line: Option<usize>,
+    text: String,
+    /// The error that prevented parsing an iCalendar object, as opposed
+    /// to one of the dates it contains.
+    calendar_error: Option<ParseCalendarError>,
+}
+
+impl InvalidHoliday {
+    fn new(line: usize, text: &str) -> Self {
+        Self {
+            line: Some(line),
+            text: text.to_string(),
+            calendar_error: None,
+        }
+    }
+
+    fn malformed_calendar(err: ParseCalendarError) -> Self {
+        Self {
+            line: Some(err.line()),
+            text: err.kind().to_string(),
+            calendar_error: Some(err),
+        }
+    }
+
+    /// Returns the number of the malformed line, starting from 1,
+    /// if it is known.
+    pub const fn line(&self) -> Option<usize> {
+        self.line
+    }
+}
+
+impl Display for InvalidHoliday {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(err) = &self.calendar_error {
+            return write!(
+                f,
+                "malformed holiday calendar at line {}: {}",
+                err.line(),
+                err.kind()
+            );
+        }
+        match self.line {
+            Some(line) => write!(
+                f,
+                "cannot parse holiday date at line {}: `{}`",
+                line, self.text
+            ),
+            None => write!(f, "cannot parse holiday date: `{}`", self.text),
+        }
+    }
+}
+
+impl Error for InvalidHoliday {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.calendar_error
+            .as_ref()
+            .map(|err| err as &(dyn Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::holidays::HolidayCalendar;
+    use chrono::NaiveDate;
+    use std::error::Error;
+
+    #[test]
+    fn public_holidays() {
+        let holidays = HolidayCalendar::public_holidays(2022).unwrap();
+        assert!(holidays.contains(NaiveDate::from_ymd_opt(2022, 10, 12).unwrap()));
+        assert!(holidays.contains(NaiveDate::from_ymd_opt(2022, 12, 8).unwrap()));
+        assert!(!holidays.contains(NaiveDate::from_ymd_opt(2022, 10, 13).unwrap()));
+        assert!(HolidayCalendar::public_holidays(1990).is_none());
+    }
+
+    #[test]
+    fn extend_holidays() {
+        let mut holidays = HolidayCalendar::public_holidays(2022).unwrap();
+        let non_teaching = HolidayCalendar::from_csv(
+            "2022-12-07
+2022-12-08
+",
+        )
+        .unwrap();
+        holidays.extend(non_teaching.dates());
+        assert!(holidays.contains(NaiveDate::from_ymd_opt(2022, 12, 6).unwrap()));
+        assert!(holidays.contains(NaiveDate::from_ymd_opt(2022, 12, 7).unwrap()));
+        assert_eq!(holidays.dates().count(), 14);
+    }
+
+    #[test]
+    fn csv_holidays() {
+        let holidays =
+            HolidayCalendar::from_csv("# comment\n\n2022-11-01,All Saints\n2022-12-06\n").unwrap();
+        assert_eq!(
+            holidays.dates().collect::<Vec<_>>(),
+            vec![
+                NaiveDate::from_ymd_opt(2022, 11, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2022, 12, 6).unwrap()
+            ]
+        );
+
+        let err = HolidayCalendar::from_csv("2022-11-01\n01/11/2022,All Saints").unwrap_err();
+        assert_eq!(err.line(), Some(2));
+    }
+
+    #[test]
+    fn ics_holidays() {
+        let holidays = HolidayCalendar::from_ics(
+            "BEGIN:VCALENDAR\r\n\
+            DTSTART:20000101\r\n\
+            BEGIN:VTIMEZONE\r\n\
+            TZID:Europe/Madrid\r\n\
+            BEGIN:STANDARD\r\n\
+            DTSTART:19961027T030000\r\n\
+            END:STANDARD\r\n\
+            END:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20221012\r\n\
+            SUMMARY:Fiesta Nacional\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;TZID=Europe/Madrid:2022110\r\n 1T000000\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            holidays.dates().collect::<Vec<_>>(),
+            vec![
+                NaiveDate::from_ymd_opt(2022, 10, 12).unwrap(),
+                NaiveDate::from_ymd_opt(2022, 11, 1).unwrap()
+            ]
+        );
+
+        // Dates must be inside a well-formed calendar object.
+        let err =
+            HolidayCalendar::from_ics("BEGIN:VEVENT\nDTSTART:20221012\nEND:VEVENT").unwrap_err();
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(
+            err.to_string(),
+            "malformed holiday calendar at line 3: expected a VCALENDAR component"
+        );
+        assert!(err.source().is_some());
+
+        let err = HolidayCalendar::from_ics(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART:2022\nEND:VEVENT\nEND:VCALENDAR",
+        )
+        .unwrap_err();
+        assert_eq!(err.line(), None);
+        assert_eq!(err.to_string(), "cannot parse holiday date: `DTSTART:2022`");
+        assert!(err.source().is_none());
+    }
+}
@@ -1,6 +1,7 @@
-use crate::ical::{format_date_time, Component, Prop};
-use chrono::{DateTime, Duration};
-use chrono_tz::Tz;
+use crate::ical::{is_color_name, Component, DateTimeValue, Param, Prop, Value};
+use chrono::{DateTime, Duration, Offset, TimeZone as _, Utc, Weekday};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use itertools::Itertools;
 use std::fmt::{Display, Formatter};
 use std::num::NonZeroU32;
 use std::slice;
@@ -15,7 +16,19 @@ pub struct Event {
     summary: Option<String>,
     description: Option<String>,
     location: Option<String>,
+    geo: Option<Geo>,
+    categories: Vec<String>,
+    url: Option<String>,
+    status: Option<EventStatus>,
+    class: Option<Classification>,
+    transparency: Option<Transparency>,
+    sequence: Option<u32>,
+    color: Option<String>,
     recurrence: Option<Recurrence>,
+    recurrence_id: Option<DateTime<Tz>>,
+    inclusions: Vec<DateTime<Tz>>,
+    exclusions: Vec<DateTime<Tz>>,
+    alarms: Vec<Alarm>,
     // The following two properties are mutually exclusive
     end: Option<DateTime<Tz>>,
     duration: Option<Duration>,
@@ -35,7 +48,19 @@ impl Event {
             summary: None,
             description: None,
             location: None,
+            geo: None,
+            categories: Vec::new(),
+            url: None,
+            status: None,
+            class: None,
+            transparency: None,
+            sequence: None,
+            color: None,
             recurrence: None,
+            recurrence_id: None,
+            inclusions: Vec::new(),
+            exclusions: Vec::new(),
+            alarms: Vec::new(),
             end: None,
             duration: None,
         }
@@ -66,12 +91,94 @@ impl Event {
         self
     }
 
+    /// Defines the global position of the venue, in degrees.
+    pub fn geo(mut self, latitude: f64, longitude: f64) -> Self {
+        self.geo = Some(Geo::new(latitude, longitude));
+        self
+    }
+
+    /// Adds a category or subtype of the activity.
+    pub fn category<C: Into<String>>(mut self, category: C) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    /// Defines the URI of a more dynamic rendition of the event.
+    pub fn url<U: Into<String>>(mut self, uri: U) -> Self {
+        self.url = Some(uri.into());
+        self
+    }
+
+    /// Defines the confirmation status of the event.
+    pub fn status(mut self, status: EventStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Defines the access classification of the event.
+    pub fn class(mut self, class: Classification) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    /// Defines whether the event consumes time on the calendar, which
+    /// affects free/busy time searches.
+    pub fn transparency(mut self, transparency: Transparency) -> Self {
+        self.transparency = Some(transparency);
+        self
+    }
+
+    /// Defines the revision sequence number of the event, which
+    /// must be incremented whenever the event is rescheduled.
+    pub fn sequence(mut self, sequence: u32) -> Self {
+        assert!(
+            sequence <= i32::MAX as u32,
+            "sequence number must fit an integer value; got {}",
+            sequence
+        );
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// Defines the color used to display the event, given as
+    /// a CSS3 color name such as `turquoise`.
+    pub fn color(mut self, color: &str) -> Self {
+        assert!(is_color_name(color), "invalid CSS3 color name '{}'", color);
+        self.color = Some(color.to_ascii_lowercase());
+        self
+    }
+
     /// Defines the recurrence rule for the event.
     pub fn recurrence(mut self, recurrence: Recurrence) -> Self {
         self.recurrence = Some(recurrence);
         self
     }
 
+    /// Defines the original start of the occurrence of a recurring event
+    /// (with the same UID) that this event overrides.
+    pub fn recurrence_id(mut self, start: DateTime<Tz>) -> Self {
+        self.recurrence_id = Some(start);
+        self
+    }
+
+    /// Adds an occurrence outside of the recurrence rule, given its start.
+    pub fn include(mut self, start: DateTime<Tz>) -> Self {
+        self.inclusions.push(start);
+        self
+    }
+
+    /// Excludes an occurrence of the recurrence rule, given its start.
+    pub fn exclude(mut self, start: DateTime<Tz>) -> Self {
+        self.exclusions.push(start);
+        self
+    }
+
+    /// Adds a reminder of the event.
+    pub fn alarm(mut self, alarm: Alarm) -> Self {
+        self.alarms.push(alarm);
+        self
+    }
+
     /// Defines the date and time by which the event ends.
     pub fn end(mut self, end: DateTime<Tz>) -> Self {
         assert!(
@@ -101,55 +208,285 @@ impl Event {
 impl From<Event> for Component {
     fn from(event: Event) -> Self {
         let mut props = vec![
-            Prop::date_time("DTSTAMP", &event.last_modified),
+            // The DTSTAMP and CREATED properties must be specified in UTC time.
+            Prop::utc_date_time("DTSTAMP", &event.last_modified.with_timezone(&Utc)),
             Prop::text("UID", slice::from_ref(&event.uid)),
             Prop::date_time("DTSTART", &event.start),
         ];
         props.extend(
             [
+                event.created_on.map(|created_on| {
+                    Prop::utc_date_time("CREATED", &created_on.with_timezone(&Utc))
+                }),
+                event
+                    .summary
+                    .map(|summary| Prop::new("SUMMARY", Value::Text(summary))),
                 event
-                    .created_on
-                    .map(|created_on| Prop::date_time("CREATED", &created_on)),
-                event.summary.map(|summary| Prop::new("SUMMARY", summary)),
-                event.description.map(|desc| Prop::new("DESCRIPTION", desc)),
+                    .description
+                    .map(|desc| Prop::new("DESCRIPTION", Value::Text(desc))),
                 event
                     .location
-                    .map(|location| Prop::new("LOCATION", location)),
+                    .map(|location| Prop::new("LOCATION", Value::Text(location))),
+                event.geo.map(|geo| Prop::new("GEO", Value::Geo(geo))),
+                Some(&event.categories)
+                    .filter(|categories| !categories.is_empty())
+                    .map(|categories| Prop::text("CATEGORIES", categories)),
+                event.url.map(|url| Prop::new("URL", Value::Uri(url))),
+                event
+                    .status
+                    .map(|status| Prop::text("STATUS", &[status.name()])),
+                event
+                    .class
+                    .map(|class| Prop::text("CLASS", &[class.name()])),
+                event
+                    .transparency
+                    .map(|transparency| Prop::text("TRANSP", &[transparency.name()])),
+                event
+                    .sequence
+                    .map(|sequence| Prop::new("SEQUENCE", Value::Integer(sequence as i32))),
+                event
+                    .color
+                    .map(|color| Prop::new("COLOR", Value::Text(color))),
                 event.end.map(|end| Prop::date_time("DTEND", &end)),
-                // The ISO 8601 duration format is compatible with RFC 5545, except
-                // for the year and week designators, which chrono doesn't use.
                 event
                     .duration
-                    .map(|duration| Prop::new("DURATION", duration.to_string())),
+                    .map(|duration| Prop::new("DURATION", Value::Duration(duration))),
                 event
                     .recurrence
-                    .map(|rrule| Prop::new("RRULE", rrule.to_string())),
+                    .map(|rrule| Prop::new("RRULE", Value::Recur(Box::new(rrule)))),
+                Some(&event.inclusions)
+                    .filter(|inclusions| !inclusions.is_empty())
+                    .map(|inclusions| Prop::date_times("RDATE", inclusions)),
+                Some(&event.exclusions)
+                    .filter(|exclusions| !exclusions.is_empty())
+                    .map(|exclusions| Prop::date_times("EXDATE", exclusions)),
+                event
+                    .recurrence_id
+                    .map(|start| Prop::date_time("RECURRENCE-ID", &start)),
             ]
             .into_iter()
             .flatten(),
         );
-        Component::new("VEVENT", props)
+        let mut component = Component::new("VEVENT", props);
+        component
+            .components_mut()
+            .extend(event.alarms.into_iter().map(Component::from));
+        component
+    }
+}
+
+/// The global position of an [`Event`], in degrees.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Geo {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl Geo {
+    /// Creates the position at the given `latitude` and `longitude`.
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        assert!(
+            (-90.0..=90.0).contains(&latitude),
+            "invalid latitude {}",
+            latitude
+        );
+        assert!(
+            (-180.0..=180.0).contains(&longitude),
+            "invalid longitude {}",
+            longitude
+        );
+        Self {
+            latitude,
+            longitude,
+        }
     }
 }
 
+// The coordinates are never NaN.
+impl Eq for Geo {}
+
+impl Display for Geo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Section 3.8.1.6 recommends 6 decimal places.
+        write!(f, "{:.6};{:.6}", self.latitude, self.longitude)
+    }
+}
+
+/// The confirmation status of an [`Event`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum EventStatus {
+    Tentative,
+    Confirmed,
+    Cancelled,
+}
+
+impl EventStatus {
+    /// Returns the value of the `STATUS` property.
+    pub const fn name(&self) -> &'static str {
+        match *self {
+            EventStatus::Tentative => "TENTATIVE",
+            EventStatus::Confirmed => "CONFIRMED",
+            EventStatus::Cancelled => "CANCELLED",
+        }
+    }
+}
+
+/// The access classification of an [`Event`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Classification {
+    Public,
+    Private,
+    Confidential,
+}
+
+impl Classification {
+    /// Returns the value of the `CLASS` property.
+    pub const fn name(&self) -> &'static str {
+        match *self {
+            Classification::Public => "PUBLIC",
+            Classification::Private => "PRIVATE",
+            Classification::Confidential => "CONFIDENTIAL",
+        }
+    }
+}
+
+/// Whether an [`Event`] consumes time on a calendar.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Transparency {
+    /// The event blocks time on the calendar.
+    Opaque,
+    /// The event doesn't block time on the calendar.
+    Transparent,
+}
+
+impl Transparency {
+    /// Returns the value of the `TRANSP` property.
+    pub const fn name(&self) -> &'static str {
+        match *self {
+            Transparency::Opaque => "OPAQUE",
+            Transparency::Transparent => "TRANSPARENT",
+        }
+    }
+}
+
+/// A reminder of an [`Event`].
+#[derive(Debug, Eq, PartialEq)]
+pub struct Alarm {
+    action: AlarmAction,
+    trigger: Trigger,
+}
+
+impl Alarm {
+    /// Creates an alarm that displays the given `description`
+    /// to the user when triggered.
+    pub fn display<D: Into<String>>(trigger: Trigger, description: D) -> Self {
+        Self {
+            action: AlarmAction::Display(description.into()),
+            trigger,
+        }
+    }
+
+    /// Creates an alarm that plays a sound when triggered.
+    pub fn audio(trigger: Trigger) -> Self {
+        Self {
+            action: AlarmAction::Audio,
+            trigger,
+        }
+    }
+}
+
+impl From<Alarm> for Component {
+    fn from(alarm: Alarm) -> Self {
+        let trigger = match alarm.trigger {
+            Trigger::Relative(offset) => Prop::new("TRIGGER", Value::Duration(offset)),
+            Trigger::Absolute(date_time) => {
+                let mut prop = Prop::utc_date_time("TRIGGER", &date_time);
+                prop.params_mut()
+                    .push(Param::new("VALUE", vec!["DATE-TIME".to_string()]));
+                prop
+            }
+        };
+        let props = match alarm.action {
+            AlarmAction::Display(description) => vec![
+                Prop::text("ACTION", &["DISPLAY"]),
+                trigger,
+                Prop::text("DESCRIPTION", &[description]),
+            ],
+            AlarmAction::Audio => vec![Prop::text("ACTION", &["AUDIO"]), trigger],
+        };
+        Component::new("VALARM", props)
+    }
+}
+
+/// The action invoked when an [`Alarm`] is triggered.
+#[derive(Debug, Eq, PartialEq)]
+enum AlarmAction {
+    Display(String),
+    Audio,
+}
+
+/// Specifies when an [`Alarm`] is triggered.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Trigger {
+    /// Triggers the alarm at an offset from the start of the event,
+    /// which is negative for alarms that trigger before the event.
+    Relative(Duration),
+    /// Triggers the alarm at the given date and time.
+    Absolute(DateTime<Utc>),
+}
+
 /// A recurrence rule specification.
+///
+/// The `BYxxx` rule parts expand or limit the occurrences defined by the
+/// frequency and interval of the rule. The builder methods panic if a
+/// value is out of range or the rule part cannot be used with the
+/// frequency of the rule, as defined in section 3.3.10 of RFC 5545.
 #[derive(Debug, Eq, PartialEq)]
 pub struct Recurrence {
     frequency: TimeUnit,
     until: Option<DateTime<Tz>>,
     count: Option<NonZeroU32>,
     interval: Option<NonZeroU32>,
+    by_second: Vec<u8>,
+    by_minute: Vec<u8>,
+    by_hour: Vec<u8>,
+    by_day: Vec<(Option<i8>, Weekday)>,
+    by_month_day: Vec<i8>,
+    by_year_day: Vec<i16>,
+    by_week_no: Vec<i8>,
+    by_month: Vec<u8>,
+    by_set_pos: Vec<i16>,
+    week_start: Option<Weekday>,
 }
 
 impl Recurrence {
     /// Creates a recurrence rule that repeats with the specified
-    /// frequency until the given date and time (inclusive).
-    pub fn until(frequency: TimeUnit, until: DateTime<Tz>) -> Self {
+    /// frequency indefinitely.
+    pub fn new(frequency: TimeUnit) -> Self {
         Self {
             frequency,
-            until: Some(until),
+            until: None,
             count: None,
             interval: None,
+            by_second: Vec::new(),
+            by_minute: Vec::new(),
+            by_hour: Vec::new(),
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_year_day: Vec::new(),
+            by_week_no: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: None,
+        }
+    }
+
+    /// Creates a recurrence rule that repeats with the specified
+    /// frequency until the given date and time (inclusive).
+    pub fn until(frequency: TimeUnit, until: DateTime<Tz>) -> Self {
+        Self {
+            until: Some(until),
+            ..Self::new(frequency)
         }
     }
 
@@ -159,10 +496,8 @@ impl Recurrence {
     /// The `start` of an [`Event`] counts as the first occurrence.
     pub fn times(frequency: TimeUnit, count: u32) -> Self {
         Self {
-            frequency,
-            until: None,
             count: Some(NonZeroU32::new(count).expect("recurrence count must be positive")),
-            interval: None,
+            ..Self::new(frequency)
         }
     }
 
@@ -170,29 +505,361 @@ impl Recurrence {
     ///
     /// For example, within a rule with daily frequency, a value
     /// of `8` means the event occurs every eight days.
-    pub fn interval(&mut self, interval: u32) {
+    pub fn interval(&mut self, interval: u32) -> &mut Self {
         self.interval =
             Some(NonZeroU32::new(interval).expect("recurrence interval must be positive"));
+        self
+    }
+
+    /// Adds a second of the minute, from 0 to 60, to the `BYSECOND` rule part.
+    pub fn by_second(&mut self, second: u8) -> &mut Self {
+        assert!(second <= 60, "invalid second {}", second);
+        self.by_second.push(second);
+        self
+    }
+
+    /// Adds a minute of the hour, from 0 to 59, to the `BYMINUTE` rule part.
+    pub fn by_minute(&mut self, minute: u8) -> &mut Self {
+        assert!(minute < 60, "invalid minute {}", minute);
+        self.by_minute.push(minute);
+        self
+    }
+
+    /// Adds an hour of the day, from 0 to 23, to the `BYHOUR` rule part.
+    pub fn by_hour(&mut self, hour: u8) -> &mut Self {
+        assert!(hour < 24, "invalid hour {}", hour);
+        self.by_hour.push(hour);
+        self
+    }
+
+    /// Adds every `weekday` within the period of the rule
+    /// to the `BYDAY` rule part.
+    pub fn by_day(&mut self, weekday: Weekday) -> &mut Self {
+        self.by_day.push((None, weekday));
+        self
+    }
+
+    /// Adds the `nth` occurrence of `weekday` within the month or year
+    /// to the `BYDAY` rule part, counting from the end if negative.
+    pub fn by_nth_day(&mut self, nth: i8, weekday: Weekday) -> &mut Self {
+        assert!(
+            matches!(self.frequency, TimeUnit::Month | TimeUnit::Year),
+            "numbered BYDAY requires a monthly or yearly rule"
+        );
+        assert!(
+            self.by_week_no.is_empty(),
+            "numbered BYDAY cannot be used with BYWEEKNO"
+        );
+        assert!(
+            valid_ordinal(nth.into(), 53),
+            "invalid weekday number {}",
+            nth
+        );
+        self.by_day.push((Some(nth), weekday));
+        self
+    }
+
+    /// Adds a day of the month to the `BYMONTHDAY` rule part,
+    /// counting from the end if negative.
+    pub fn by_month_day(&mut self, day: i8) -> &mut Self {
+        assert!(
+            self.frequency != TimeUnit::Week,
+            "BYMONTHDAY cannot be used with a weekly rule"
+        );
+        assert!(
+            valid_ordinal(day.into(), 31),
+            "invalid day of month {}",
+            day
+        );
+        self.by_month_day.push(day);
+        self
+    }
+
+    /// Adds a day of the year to the `BYYEARDAY` rule part,
+    /// counting from the end if negative.
+    pub fn by_year_day(&mut self, day: i16) -> &mut Self {
+        assert!(
+            !matches!(
+                self.frequency,
+                TimeUnit::Day | TimeUnit::Week | TimeUnit::Month
+            ),
+            "BYYEARDAY cannot be used with a daily, weekly or monthly rule"
+        );
+        assert!(valid_ordinal(day, 366), "invalid day of year {}", day);
+        self.by_year_day.push(day);
+        self
+    }
+
+    /// Adds a week of the year to the `BYWEEKNO` rule part,
+    /// counting from the end if negative.
+    pub fn by_week_no(&mut self, week: i8) -> &mut Self {
+        assert!(
+            self.frequency == TimeUnit::Year,
+            "BYWEEKNO requires a yearly rule"
+        );
+        assert!(
+            self.by_day.iter().all(|(nth, _)| nth.is_none()),
+            "BYWEEKNO cannot be used with numbered BYDAY"
+        );
+        assert!(
+            valid_ordinal(week.into(), 53),
+            "invalid week number {}",
+            week
+        );
+        self.by_week_no.push(week);
+        self
+    }
+
+    /// Adds a month of the year, from 1 to 12, to the `BYMONTH` rule part.
+    pub fn by_month(&mut self, month: u8) -> &mut Self {
+        assert!((1..=12).contains(&month), "invalid month {}", month);
+        self.by_month.push(month);
+        self
+    }
+
+    /// Adds an occurrence within the set of occurrences of each period
+    /// of the rule to the `BYSETPOS` rule part, counting from the end if
+    /// negative. Another `BYxxx` rule part must be set beforehand.
+    pub fn by_set_pos(&mut self, position: i16) -> &mut Self {
+        assert!(
+            !(self.by_second.is_empty()
+                && self.by_minute.is_empty()
+                && self.by_hour.is_empty()
+                && self.by_day.is_empty()
+                && self.by_month_day.is_empty()
+                && self.by_year_day.is_empty()
+                && self.by_week_no.is_empty()
+                && self.by_month.is_empty()),
+            "BYSETPOS requires another BYxxx rule part"
+        );
+        assert!(
+            valid_ordinal(position, 366),
+            "invalid set position {}",
+            position
+        );
+        self.by_set_pos.push(position);
+        self
+    }
+
+    /// Defines the day on which the work week starts, which
+    /// defaults to Monday.
+    pub fn week_start(&mut self, weekday: Weekday) -> &mut Self {
+        self.week_start = Some(weekday);
+        self
+    }
+}
+
+/// Tests if `value` is a non-zero ordinal within `-max..=max`.
+fn valid_ordinal(value: i16, max: i16) -> bool {
+    value != 0 && value.abs() <= max
+}
+
+/// Returns the two-letter abbreviation of a weekday used in
+/// recurrence rules.
+const fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
     }
 }
 
 impl Display for Recurrence {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fn write_part<T: Display>(
+            f: &mut Formatter<'_>,
+            name: &str,
+            values: &[T],
+        ) -> std::fmt::Result {
+            if !values.is_empty() {
+                write!(f, ";{}={}", name, values.iter().join(","))?;
+            }
+            Ok(())
+        }
+
         write!(f, "FREQ={}", self.frequency.recurrence_freq())?;
         if let Some(until) = &self.until {
-            // The UNTIL parameter must be specified in UTC time.
-            let utc = until.with_timezone(&Tz::UTC);
-            write!(f, ";UNTIL={}", format_date_time(&utc))?;
-        } else {
-            write!(f, ";COUNT={}", self.count.unwrap())?;
+            // The UNTIL parameter must be specified in UTC time,
+            // since the start of the event has a time zone.
+            let utc = DateTimeValue::Utc(until.with_timezone(&Utc));
+            write!(f, ";UNTIL={}", utc)?;
+        } else if let Some(count) = &self.count {
+            write!(f, ";COUNT={}", count)?;
         }
         if let Some(interval) = &self.interval {
             write!(f, ";INTERVAL={}", interval)?;
         }
+        write_part(f, "BYSECOND", &self.by_second)?;
+        write_part(f, "BYMINUTE", &self.by_minute)?;
+        write_part(f, "BYHOUR", &self.by_hour)?;
+        let by_day: Vec<_> = self
+            .by_day
+            .iter()
+            .map(|&(nth, weekday)| match nth {
+                Some(nth) => format!("{}{}", nth, weekday_code(weekday)),
+                None => weekday_code(weekday).to_string(),
+            })
+            .collect();
+        write_part(f, "BYDAY", &by_day)?;
+        write_part(f, "BYMONTHDAY", &self.by_month_day)?;
+        write_part(f, "BYYEARDAY", &self.by_year_day)?;
+        write_part(f, "BYWEEKNO", &self.by_week_no)?;
+        write_part(f, "BYMONTH", &self.by_month)?;
+        write_part(f, "BYSETPOS", &self.by_set_pos)?;
+        if let Some(week_start) = self.week_start {
+            write!(f, ";WKST={}", weekday_code(week_start))?;
+        }
         Ok(())
     }
 }
 
+/// The definition of a time zone, consisting of the observances of
+/// standard and daylight saving time within a date span.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TimeZone {
+    time_zone: Tz,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    transitions: Vec<DateTime<Utc>>,
+}
+
+impl TimeZone {
+    /// Creates the definition of `time_zone` that covers the dates
+    /// and times from `start` to `end`.
+    ///
+    /// The transitions of the time zone are found on creation, so the
+    /// definition can be converted into a [`Component`] repeatedly.
+    pub fn new(time_zone: Tz, start: DateTime<Tz>, end: DateTime<Tz>) -> Self {
+        assert!(start <= end, "time zone span must not be reversed");
+        let mut definition = Self {
+            time_zone,
+            start: start.with_timezone(&Utc),
+            end: end.with_timezone(&Utc),
+            transitions: Vec::new(),
+        };
+        definition.transitions = definition.find_transitions();
+        definition
+    }
+
+    /// Returns the instants at which the UTC offset of the time zone
+    /// changes within the span, preceded by the last change before
+    /// the span (if it occurred within the previous year).
+    fn find_transitions(&self) -> Vec<DateTime<Utc>> {
+        // chrono-tz doesn't expose its transitions, so compare the offsets
+        // a week apart (no time zone changes twice within a week), and
+        // bisect the weeks in which they differ. The last change before
+        // the span is found by scanning backwards from its start.
+        let step = Duration::weeks(1);
+        let limit = self.start - Duration::days(366);
+        let mut probe = self.start;
+        let mut transitions = Vec::new();
+        while probe > limit {
+            let previous = (probe - step).max(limit);
+            if self.offsets(previous) != self.offsets(probe) {
+                transitions.push(self.bisect(previous, probe));
+                break;
+            }
+            probe = previous;
+        }
+        let mut probe = self.start;
+        while probe < self.end {
+            let next = (probe + step).min(self.end);
+            if self.offsets(probe) != self.offsets(next) {
+                transitions.push(self.bisect(probe, next));
+            }
+            probe = next;
+        }
+        transitions
+    }
+
+    /// Finds the first instant within `(before, after]` that has
+    /// the offsets in effect at `after`.
+    fn bisect(&self, mut before: DateTime<Utc>, mut after: DateTime<Utc>) -> DateTime<Utc> {
+        let offsets = self.offsets(before);
+        while after - before > Duration::seconds(1) {
+            let middle = before + (after - before) / 2;
+            if self.offsets(middle) == offsets {
+                before = middle;
+            } else {
+                after = middle;
+            }
+        }
+        after
+    }
+
+    /// Returns the UTC and daylight saving time offsets in seconds
+    /// in effect at the given instant.
+    fn offsets(&self, instant: DateTime<Utc>) -> (i32, i64) {
+        let offset = self
+            .time_zone
+            .offset_from_utc_datetime(&instant.naive_utc());
+        (
+            offset.fix().local_minus_utc(),
+            offset.dst_offset().num_seconds(),
+        )
+    }
+
+    /// Creates the `STANDARD` or `DAYLIGHT` sub-component of the observance
+    /// that starts at `onset`, after the observance in effect at `previous`.
+    fn observance_component(&self, previous: DateTime<Utc>, onset: DateTime<Utc>) -> Component {
+        let (offset_from, _) = self.offsets(previous);
+        let (offset_to, daylight_offset) = self.offsets(onset);
+        let is_daylight = daylight_offset != 0;
+        let name = self
+            .time_zone
+            .offset_from_utc_datetime(&onset.naive_utc())
+            .abbreviation()
+            .to_string();
+        // The onset is expressed in the local time of the previous observance.
+        let local_onset = onset.naive_utc() + Duration::seconds(offset_from.into());
+        Component::new(
+            if is_daylight { "DAYLIGHT" } else { "STANDARD" },
+            vec![
+                Prop::date_time_value("DTSTART", &DateTimeValue::Floating(local_onset)),
+                Prop::new("TZOFFSETFROM", Value::UtcOffset(offset_from)),
+                Prop::new("TZOFFSETTO", Value::UtcOffset(offset_to)),
+                Prop::text("TZNAME", slice::from_ref(&name)),
+            ],
+        )
+    }
+}
+
+impl From<TimeZone> for Component {
+    fn from(time_zone: TimeZone) -> Self {
+        Component::from(&time_zone)
+    }
+}
+
+impl From<&TimeZone> for Component {
+    fn from(time_zone: &TimeZone) -> Self {
+        let mut component = Component::new(
+            "VTIMEZONE",
+            vec![Prop::text("TZID", &[time_zone.time_zone.name()])],
+        );
+        let transitions = &time_zone.transitions;
+        let observances = component.components_mut();
+        #[allow(clippy::unnecessary_map_or)] // Option::is_none_or needs Rust 1.82
+        if transitions
+            .first()
+            .map_or(true, |&first| first > time_zone.start)
+        {
+            // The time zone doesn't change right before the span, so
+            // the observance starts with the span and keeps the offset.
+            let start = time_zone.start;
+            observances.push(time_zone.observance_component(start, start));
+        }
+        for &transition in transitions {
+            let previous = transition - Duration::seconds(1);
+            observances.push(time_zone.observance_component(previous, transition));
+        }
+        component
+    }
+}
+
 /// Named intervals of time.
 // chrono doesn't provide this enum :(
 #[derive(Debug, Eq, PartialEq)]
@@ -224,42 +891,135 @@ impl TimeUnit {
 
 #[cfg(test)]
 mod tests {
-    use crate::ical::components::{Event, Recurrence, TimeUnit};
-    use crate::ical::{Component, PropHolder};
+    use crate::ical::components::{
+        Alarm, Classification, Event, EventStatus, Recurrence, TimeUnit, TimeZone, Transparency,
+        Trigger,
+    };
+    use crate::ical::{Component, ComponentHolder, PropHolder};
     use crate::UC3M_TIMEZONE;
-    use chrono::{Duration, TimeZone, Utc};
+    use chrono::{DateTime, Duration, Utc, Weekday};
+    use chrono_tz::Tz;
 
     #[test]
     fn event_builder() {
         let last_modified = Utc::now().with_timezone(&UC3M_TIMEZONE);
-        let start = last_modified.clone() + Duration::days(3);
+        let start = last_modified + Duration::days(3);
         let event = Event::new("1234", last_modified, start)
             .summary("Important Meeting")
             .description("A very important meeting.")
-            .location("Room 101")
+            .location("Room 101; main building")
+            .category("Meeting")
+            .category("Work, urgent")
             .end(start + Duration::minutes(30));
         let component = Component::from(event);
-        assert_eq!(component.first_prop("UID").unwrap().value, "1234");
+        assert_eq!(component.first_prop("UID").unwrap().value(), "1234");
         assert_eq!(
-            component.first_prop("SUMMARY").unwrap().value,
+            component.first_prop("SUMMARY").unwrap().value(),
             "Important Meeting"
         );
         assert_eq!(
-            component.first_prop("DESCRIPTION").unwrap().value,
+            component.first_prop("DESCRIPTION").unwrap().value(),
             "A very important meeting."
         );
-        assert_eq!(component.first_prop("LOCATION").unwrap().value, "Room 101");
+        assert_eq!(
+            component.first_prop("LOCATION").unwrap().value(),
+            r"Room 101\; main building"
+        );
+        assert_eq!(
+            component.first_prop("CATEGORIES").unwrap().value(),
+            r"Meeting,Work\, urgent"
+        );
         assert!(component.has_prop("DTSTAMP"));
         assert!(component.has_prop("DTSTART"));
         assert!(component.has_prop("DTEND"));
         assert!(!component.has_prop("DURATION"));
     }
 
+    #[test]
+    fn event_props() {
+        let now = Utc::now().with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("1234", now, now)
+            .geo(40.332_472, -3.765_167)
+            .url("https://example.com/event?id=1234&lang=en")
+            .status(EventStatus::Cancelled)
+            .class(Classification::Private)
+            .transparency(Transparency::Transparent)
+            .sequence(3)
+            .color("DarkOrange");
+        let props: String = Component::from(event)
+            .props()
+            .iter()
+            .skip(3)
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            props,
+            "GEO:40.332472;-3.765167\r\n\
+            URL:https://example.com/event?id=1234&lang=en\r\n\
+            STATUS:CANCELLED\r\n\
+            CLASS:PRIVATE\r\n\
+            TRANSP:TRANSPARENT\r\n\
+            SEQUENCE:3\r\n\
+            COLOR:darkorange\r\n"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_latitude() {
+        let now = Utc::now().with_timezone(&UC3M_TIMEZONE);
+        Event::new("test", now, now).geo(-91.0, 0.0);
+    }
+
+    #[test]
+    fn excluded_occurrences() {
+        let start = "2022-09-05T13:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("lecture", start, start)
+            .recurrence(Recurrence::times(TimeUnit::Week, 4))
+            .exclude(start + Duration::weeks(1))
+            .exclude(start + Duration::weeks(2));
+        let component = Component::from(event);
+        assert_eq!(
+            component.first_prop("EXDATE").unwrap().to_string(),
+            "EXDATE;TZID=Europe/Madrid:20220912T150000,20220919T150000\r\n"
+        );
+
+        let event = Event::new("lecture", start, start);
+        assert!(!Component::from(event).has_prop("EXDATE"));
+
+        let event = Event::new("lecture", start, start)
+            .recurrence(Recurrence::times(TimeUnit::Week, 4))
+            .include(start + Duration::days(2));
+        assert_eq!(
+            Component::from(event)
+                .first_prop("RDATE")
+                .unwrap()
+                .to_string(),
+            "RDATE;TZID=Europe/Madrid:20220907T150000\r\n"
+        );
+
+        // A cancelled occurrence overrides the one it replaces.
+        let cancelled = start + Duration::weeks(3);
+        let event = Event::new("lecture", start, cancelled)
+            .recurrence_id(cancelled)
+            .status(EventStatus::Cancelled);
+        assert_eq!(
+            Component::from(event)
+                .first_prop("RECURRENCE-ID")
+                .unwrap()
+                .to_string(),
+            "RECURRENCE-ID;TZID=Europe/Madrid:20220926T150000\r\n"
+        );
+    }
+
     #[test]
     #[should_panic]
     fn end_and_duration() {
         let now = Utc::now().with_timezone(&UC3M_TIMEZONE);
-        Event::new("test", now.clone(), now.clone())
+        Event::new("test", now, now)
             .end(now + Duration::hours(2))
             .duration(Duration::hours(2));
     }
@@ -268,7 +1028,7 @@ mod tests {
     #[should_panic]
     fn duration_and_end() {
         let now = Utc::now().with_timezone(&UC3M_TIMEZONE);
-        Event::new("test", now.clone(), now.clone())
+        Event::new("test", now, now)
             .duration(Duration::hours(3))
             .end(now + Duration::hours(3));
     }
@@ -278,15 +1038,174 @@ mod tests {
         let rule = Recurrence::times(TimeUnit::Day, 3);
         assert_eq!(rule.to_string(), "FREQ=DAILY;COUNT=3");
 
-        let last_date = Utc
-            .ymd(2022, 8, 19)
-            .and_hms(20, 30, 15)
+        let last_date = "2022-08-19T20:30:15Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
             .with_timezone(&UC3M_TIMEZONE);
         let rule = Recurrence::until(TimeUnit::Week, last_date);
-        assert_eq!(rule.to_string(), "FREQ=WEEKLY;UNTIL=20220819T203015");
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;UNTIL=20220819T203015Z");
+
+        // Example from section 3.8.5.3 of RFC 5545.
+        let last_date = "1997-12-24T00:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let rule = Recurrence::until(TimeUnit::Day, last_date);
+        assert_eq!(rule.to_string(), "FREQ=DAILY;UNTIL=19971224T000000Z");
 
         let mut rule = Recurrence::times(TimeUnit::Hour, 10);
         rule.interval(2);
         assert_eq!(rule.to_string(), "FREQ=HOURLY;COUNT=10;INTERVAL=2");
     }
+
+    #[test]
+    fn recurrence_rule_parts() {
+        // Examples from section 3.8.5.3 of RFC 5545.
+        let mut rule = Recurrence::new(TimeUnit::Week);
+        rule.interval(2)
+            .by_day(Weekday::Mon)
+            .by_day(Weekday::Wed)
+            .by_day(Weekday::Fri)
+            .week_start(Weekday::Sun);
+        assert_eq!(
+            rule.to_string(),
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE,FR;WKST=SU"
+        );
+
+        let mut rule = Recurrence::times(TimeUnit::Month, 10);
+        rule.by_nth_day(1, Weekday::Fri);
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;COUNT=10;BYDAY=1FR");
+
+        let mut rule = Recurrence::new(TimeUnit::Month);
+        rule.by_nth_day(-2, Weekday::Mon).by_month(1).by_month(3);
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;BYDAY=-2MO;BYMONTH=1,3");
+
+        let mut rule = Recurrence::new(TimeUnit::Year);
+        rule.by_week_no(20).by_day(Weekday::Mon);
+        assert_eq!(rule.to_string(), "FREQ=YEARLY;BYDAY=MO;BYWEEKNO=20");
+
+        let mut rule = Recurrence::new(TimeUnit::Year);
+        rule.by_year_day(1).by_year_day(100).by_year_day(200);
+        assert_eq!(rule.to_string(), "FREQ=YEARLY;BYYEARDAY=1,100,200");
+
+        let mut rule = Recurrence::new(TimeUnit::Month);
+        for weekday in [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ] {
+            rule.by_day(weekday);
+        }
+        rule.by_set_pos(-1);
+        assert_eq!(
+            rule.to_string(),
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1"
+        );
+
+        let mut rule = Recurrence::new(TimeUnit::Day);
+        rule.by_hour(9)
+            .by_hour(10)
+            .by_minute(0)
+            .by_minute(20)
+            .by_minute(40);
+        assert_eq!(rule.to_string(), "FREQ=DAILY;BYMINUTE=0,20,40;BYHOUR=9,10");
+
+        let mut rule = Recurrence::new(TimeUnit::Month);
+        rule.by_month_day(-3);
+        assert_eq!(rule.to_string(), "FREQ=MONTHLY;BYMONTHDAY=-3");
+    }
+
+    #[test]
+    #[should_panic]
+    fn numbered_weekday_in_weekly_rule() {
+        Recurrence::new(TimeUnit::Week).by_nth_day(2, Weekday::Mon);
+    }
+
+    #[test]
+    #[should_panic]
+    fn week_number_in_monthly_rule() {
+        Recurrence::new(TimeUnit::Month).by_week_no(10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn month_day_in_weekly_rule() {
+        Recurrence::new(TimeUnit::Week).by_month_day(1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_position_without_rule_parts() {
+        Recurrence::new(TimeUnit::Month).by_set_pos(1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_month_day() {
+        Recurrence::new(TimeUnit::Month).by_month_day(0);
+    }
+
+    #[test]
+    fn time_zone_observances() {
+        let start = "2022-09-05T00:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let end = "2023-05-31T00:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let component = Component::from(TimeZone::new(UC3M_TIMEZONE, start, end));
+        assert_eq!(
+            component.to_string(),
+            "BEGIN:VTIMEZONE\r\nTZID:Europe/Madrid\r\n\
+            BEGIN:DAYLIGHT\r\nDTSTART:20220327T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nTZNAME:CEST\r\nEND:DAYLIGHT\r\n\
+            BEGIN:STANDARD\r\nDTSTART:20221030T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\nTZNAME:CET\r\nEND:STANDARD\r\n\
+            BEGIN:DAYLIGHT\r\nDTSTART:20230326T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nTZNAME:CEST\r\nEND:DAYLIGHT\r\n\
+            END:VTIMEZONE\r\n"
+        );
+
+        // Time zones without transitions have a single observance.
+        let start = start.with_timezone(&Tz::UTC);
+        let component = Component::from(TimeZone::new(Tz::UTC, start, start));
+        assert_eq!(
+            component.to_string(),
+            "BEGIN:VTIMEZONE\r\nTZID:UTC\r\n\
+            BEGIN:STANDARD\r\nDTSTART:20220905T000000\r\nTZOFFSETFROM:+0000\r\nTZOFFSETTO:+0000\r\nTZNAME:UTC\r\nEND:STANDARD\r\n\
+            END:VTIMEZONE\r\n"
+        );
+    }
+
+    #[test]
+    fn event_alarms() {
+        let start = "2022-09-05T13:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let reminder = "2022-09-05T07:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let event = Event::new("lecture", start, start)
+            .alarm(Alarm::display(
+                Trigger::Relative(-Duration::minutes(15)),
+                "Lecture in 15 minutes",
+            ))
+            .alarm(Alarm::audio(Trigger::Absolute(reminder)));
+        let component = Component::from(event);
+        let alarms: Vec<_> = component
+            .components()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            alarms,
+            [
+                "BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT15M\r\nDESCRIPTION:Lecture in 15 minutes\r\nEND:VALARM\r\n",
+                "BEGIN:VALARM\r\nACTION:AUDIO\r\nTRIGGER;VALUE=DATE-TIME:20220905T070000Z\r\nEND:VALARM\r\n"
+            ]
+        );
+        assert!(component
+            .to_string()
+            .ends_with("END:VALARM\r\nEND:VEVENT\r\n"));
+    }
 }
@@ -1,10 +1,18 @@
-pub(crate) mod components;
+pub mod components;
+mod parse;
+mod serialize;
+mod value;
 
-use chrono::{DateTime, Datelike, Timelike};
+use chrono::{DateTime, Duration, Utc};
 use chrono_tz::Tz;
 use itertools::Itertools;
-use std::fmt::{Display, Formatter, Write};
-use std::slice;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::{io, slice};
+
+pub use parse::{ParseCalendarError, ParseCalendarErrorKind};
+pub use serialize::{Chunks, Serializer};
+pub use value::{DateTimeValue, Value};
 
 /// A container of [`Prop`]s.
 ///
@@ -13,7 +21,7 @@ use std::slice;
 /// the holder. Some property types also support encoding
 /// multiple values in a single [`Prop`] by separating
 /// the values with a comma (`,`) character.
-trait PropHolder {
+pub trait PropHolder {
     /// Returns the properties held by this object.
     fn props(&self) -> &Vec<Prop>;
 
@@ -28,6 +36,30 @@ trait PropHolder {
     }
 }
 
+/// A container of nested [`Component`]s.
+pub trait ComponentHolder {
+    /// Returns the components held by this object.
+    fn components(&self) -> &Vec<Component>;
+
+    /// Searches for a component with the given name.
+    fn first_component(&self, name: &str) -> Option<&Component> {
+        self.components()
+            .iter()
+            .find(|component| component.name == name)
+    }
+
+    /// Tests if any component has the given name.
+    fn has_component(&self, name: &str) -> bool {
+        self.components()
+            .iter()
+            .any(|component| component.name == name)
+    }
+}
+
+/// The names of the components that can only be nested
+/// within other components.
+const SUB_COMPONENTS: [&str; 3] = ["VALARM", "STANDARD", "DAYLIGHT"];
+
 /// An iCalendar object consisting of a sequence of
 /// [`Prop`]s and [`Component`]s.
 ///
@@ -35,11 +67,10 @@ trait PropHolder {
 /// The components are collections of properties that
 /// express a particular calendar semantic.
 ///
-/// A [`VTIMEZONE`] component must be specified for each
-/// unique non-global `TZID` parameter value specified in
-/// the calendar object. Due to their complexity, all objects
-/// of type `Tz` are formatted using global IDs (which are
-/// prefixed with a solidus character -- `/`).
+/// A [`VTIMEZONE`](components::TimeZone) component must be
+/// specified for each unique non-global `TZID` parameter value
+/// specified in the calendar object, unless the calendar is
+/// formatted with [global IDs](Calendar::global_time_zones).
 #[derive(Debug, Eq, PartialEq)]
 pub struct Calendar {
     props: Vec<Prop>,
@@ -52,13 +83,13 @@ impl Calendar {
     /// `spec_version` is the highest version number of
     /// the iCalendar specification required to interpret
     /// the iCalendar object, and `components` is a non-empty
-    /// vector.
+    /// vector of top-level components.
     pub fn new(product: &str, spec_version: &str, components: Vec<Component>) -> Self {
         assert!(!components.is_empty(), "calendar must have >= 1 components");
+        components.iter().for_each(assert_top_level);
         Self {
             // We don't provide mutable access to the `props` vector,
-            // setting the "PRODID" and "VERSION" props should suffice
-            // for now.
+            // other properties are set by the builder methods below.
             props: vec![
                 Prop::text("PRODID", slice::from_ref(&product)),
                 Prop::text("VERSION", slice::from_ref(&spec_version)),
@@ -67,6 +98,130 @@ impl Calendar {
         }
     }
 
+    /// Defines the iCalendar object method associated with the
+    /// calendar object.
+    pub fn method(self, method: Method) -> Self {
+        self.with_props([Prop::text("METHOD", &[method.name()])])
+    }
+
+    /// Defines the name of the calendar, which calendar applications
+    /// show to the user.
+    ///
+    /// Sets both the `NAME` property defined in RFC 7986 and the
+    /// widely supported `X-WR-CALNAME` extension property.
+    pub fn name(self, name: &str) -> Self {
+        self.with_props([
+            Prop::text("NAME", slice::from_ref(&name)),
+            Prop::text("X-WR-CALNAME", slice::from_ref(&name)),
+        ])
+    }
+
+    /// Defines a textual description of the calendar.
+    ///
+    /// Sets both the `DESCRIPTION` property defined in RFC 7986 and
+    /// the widely supported `X-WR-CALDESC` extension property.
+    pub fn description(self, description: &str) -> Self {
+        self.with_props([
+            Prop::text("DESCRIPTION", slice::from_ref(&description)),
+            Prop::text("X-WR-CALDESC", slice::from_ref(&description)),
+        ])
+    }
+
+    /// Defines the default time zone of the calendar, which some
+    /// calendar applications use to display floating times.
+    ///
+    /// Sets the widely supported `X-WR-TIMEZONE` extension property.
+    pub fn time_zone(self, time_zone: Tz) -> Self {
+        self.with_props([Prop::text("X-WR-TIMEZONE", &[time_zone.name()])])
+    }
+
+    /// Defines the suggested minimum interval at which subscribed
+    /// calendar applications poll for changes.
+    ///
+    /// Sets both the `REFRESH-INTERVAL` property defined in RFC 7986
+    /// and the widely supported `X-PUBLISHED-TTL` extension property.
+    pub fn refresh_interval(self, interval: Duration) -> Self {
+        assert!(
+            interval > Duration::zero(),
+            "refresh interval must be positive; got {}",
+            interval
+        );
+        let mut refresh_interval = Prop::new("REFRESH-INTERVAL", Value::Duration(interval));
+        refresh_interval
+            .params
+            .push(Param::new("VALUE", vec!["DURATION".to_string()]));
+        self.with_props([
+            refresh_interval,
+            Prop::new("X-PUBLISHED-TTL", Value::Duration(interval)),
+        ])
+    }
+
+    /// Defines the URI from which the calendar can be refreshed.
+    pub fn source(self, uri: &str) -> Self {
+        let mut source = Prop::new("SOURCE", Value::Uri(uri.to_string()));
+        source
+            .params
+            .push(Param::new("VALUE", vec!["URI".to_string()]));
+        self.with_props([source])
+    }
+
+    /// Defines the color used to display the calendar, given as
+    /// a CSS3 color name such as `turquoise`.
+    pub fn color(self, color: &str) -> Self {
+        assert!(is_color_name(color), "invalid CSS3 color name '{}'", color);
+        self.with_props([Prop::text("COLOR", &[color.to_ascii_lowercase()])])
+    }
+
+    /// Adds the given properties, replacing the existing properties
+    /// with the same names.
+    fn with_props<const N: usize>(mut self, props: [Prop; N]) -> Self {
+        self.props
+            .retain(|prop| props.iter().all(|new| new.name != prop.name));
+        self.props.extend(props);
+        self
+    }
+
+    /// Prefixes every `TZID` parameter value with a solidus character
+    /// (`/`), which marks it as a globally unique time zone identifier.
+    ///
+    /// Calendars using global IDs don't need [`VTIMEZONE`](components::TimeZone)
+    /// components, but some calendar applications don't support them.
+    pub fn global_time_zones(mut self) -> Self {
+        globalize_tz_ids(&mut self.props, &mut self.components);
+        self
+    }
+
+    /// Adds a top-level component to the calendar.
+    pub fn component<C: Into<Component>>(mut self, component: C) -> Self {
+        let component = component.into();
+        assert_top_level(&component);
+        self.components.push(component);
+        self
+    }
+
+    /// Writes the calendar object into `writer` as it is serialized,
+    /// without building the whole object in memory. The small writes
+    /// of the serializer are buffered.
+    pub fn write_to<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        use io::Write;
+
+        let mut writer = io::BufWriter::new(writer);
+        write!(writer, "{}", self)?;
+        writer.flush()
+    }
+
+    /// Returns an iterator over the serialized chunks of the calendar
+    /// object, which can be sent while the next chunks are serialized.
+    pub fn chunks(&self) -> Chunks<&Self> {
+        Chunks::new(self)
+    }
+
+    /// Converts the calendar object into an iterator over
+    /// its serialized [chunks](Calendar::chunks).
+    pub fn into_chunks(self) -> Chunks<Self> {
+        Chunks::new(self)
+    }
+
     /// Gets a reference to the calendar components.
     pub fn components(&self) -> &Vec<Component> {
         &self.components
@@ -84,16 +239,62 @@ impl PropHolder for Calendar {
     }
 }
 
+impl ComponentHolder for Calendar {
+    fn components(&self) -> &Vec<Component> {
+        &self.components
+    }
+}
+
+/// Asserts that `component` can be placed directly within a calendar.
+fn assert_top_level(component: &Component) {
+    assert!(
+        !SUB_COMPONENTS.contains(&component.name()),
+        "{} must be nested within another component",
+        component.name()
+    );
+}
+
 impl Display for Calendar {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str("BEGIN:VCALENDAR\r\n")?;
-        for prop in &self.props {
-            f.write_str(&prop.to_string())?;
-        }
-        for component in &self.components {
-            f.write_str(&component.to_string())?;
+        Serializer::new(f).calendar(self)
+    }
+}
+
+/// The method of a [`Calendar`], which defines the scheduling
+/// transaction it represents (see RFC 5546).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Method {
+    /// Publishes the calendar without expecting replies.
+    Publish,
+    /// Requests the attendees to reply to the calendar.
+    Request,
+    /// Replies to a request.
+    Reply,
+    /// Adds instances to an existing calendar.
+    Add,
+    /// Cancels the components of the calendar.
+    Cancel,
+    /// Requests the latest version of the calendar.
+    Refresh,
+    /// Proposes changes to the calendar.
+    Counter,
+    /// Declines a counter proposal.
+    DeclineCounter,
+}
+
+impl Method {
+    /// Returns the value of the `METHOD` property.
+    pub const fn name(&self) -> &'static str {
+        match *self {
+            Method::Publish => "PUBLISH",
+            Method::Request => "REQUEST",
+            Method::Reply => "REPLY",
+            Method::Add => "ADD",
+            Method::Cancel => "CANCEL",
+            Method::Refresh => "REFRESH",
+            Method::Counter => "COUNTER",
+            Method::DeclineCounter => "DECLINECOUNTER",
         }
-        f.write_str("END:VCALENDAR\r\n")
     }
 }
 
@@ -103,16 +304,36 @@ impl Display for Calendar {
 /// For example, a component may specify an event, a to-do,
 /// time zone information, free/busy time information,
 /// an alarm, etc.
+///
+/// Some components contain nested components, such as the
+/// alarms of an event. These are only added by the typed
+/// builders (for example, [`Event::alarm`](components::Event::alarm)),
+/// which guarantee that the nesting is valid.
 #[derive(Debug, Eq, PartialEq)]
 pub struct Component {
-    name: &'static str,
+    name: Cow<'static, str>,
     props: Vec<Prop>,
+    components: Vec<Component>,
 }
 
 impl Component {
     /// Creates a component.
-    pub fn new(name: &'static str, props: Vec<Prop>) -> Self {
-        Self { name, props }
+    pub fn new<N: Into<Cow<'static, str>>>(name: N, props: Vec<Prop>) -> Self {
+        Self {
+            name: name.into(),
+            props,
+            components: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the component, such as `VEVENT`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets a mutable reference to the nested components.
+    pub(crate) fn components_mut(&mut self) -> &mut Vec<Component> {
+        &mut self.components
     }
 }
 
@@ -122,64 +343,127 @@ impl PropHolder for Component {
     }
 }
 
+impl ComponentHolder for Component {
+    fn components(&self) -> &Vec<Component> {
+        &self.components
+    }
+}
+
 impl Display for Component {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "BEGIN:{}\r\n", self.name)?;
-        for prop in &self.props {
-            f.write_str(&prop.to_string())?;
-        }
-        write!(f, "END:{}\r\n", self.name)
+        Serializer::new(f).component(self)
     }
 }
 
 /// A calendar property.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug)]
 pub struct Prop {
-    name: &'static str,
+    name: Cow<'static, str>,
     params: Vec<Param>,
-    value: String,
+    values: Vec<Value>,
 }
 
 impl Prop {
-    /// Creates a property.
-    pub fn new<V: Into<String>>(name: &'static str, value: V) -> Self {
+    /// Creates a property with a single value.
+    ///
+    /// Textual values must be passed as [`Value::Text`] to be escaped,
+    /// while preformatted values are passed as [`Value::Raw`].
+    pub fn new<N: Into<Cow<'static, str>>>(name: N, value: Value) -> Self {
+        Self::with_values(name, vec![value])
+    }
+
+    /// Creates a property with comma-separated values.
+    pub fn with_values<N: Into<Cow<'static, str>>>(name: N, values: Vec<Value>) -> Self {
         Self {
-            name,
+            name: name.into(),
             params: Vec::new(),
-            value: value.into(),
+            values,
         }
     }
 
-    /// Creates a property with comma-separated textual values, escaping
-    /// characters if necessary.
+    /// Creates a property with comma-separated textual values.
     ///
     /// The language in which the text is represented can be defined
     /// by the `LANGUAGE` property [parameter](Param).
     ///
     /// To pass a single textual value without copying, use [`slice::from_ref`].
     pub fn text<V: AsRef<str>>(name: &'static str, values: &[V]) -> Self {
-        // Escape text according to section 3.3.11.
-        let value = values
+        let values = values
             .iter()
-            .map(V::as_ref)
-            .map(|str| {
-                str.replace('\\', r"\\")
-                    .replace(';', r"\;")
-                    .replace(',', r"\,")
-                    .replace('\n', r"\n")
-            })
-            .join(",");
-        Self::new(name, value)
+            .map(|value| Value::Text(value.as_ref().to_string()))
+            .collect();
+        Self::with_values(name, values)
     }
 
     /// Creates a property with a date-time value.
     pub fn date_time(name: &'static str, date_time: &DateTime<Tz>) -> Self {
-        let global_tz_id = format!("/{}", date_time.timezone().name());
-        Self {
-            name,
-            params: vec![Param::new("TZID", vec![global_tz_id])],
-            value: format_date_time(date_time),
+        Self::date_times(name, slice::from_ref(date_time))
+    }
+
+    /// Creates a property with a date or date-time value, adding
+    /// the parameters that specify its form.
+    pub fn date_time_value(name: &'static str, value: &DateTimeValue) -> Self {
+        let mut prop = Self::new(name, Value::DateTime(*value));
+        match value {
+            DateTimeValue::Date(_) => prop
+                .params
+                .push(Param::new("VALUE", vec!["DATE".to_string()])),
+            DateTimeValue::Local(date_time) => prop.params.push(Param::new(
+                "TZID",
+                vec![date_time.timezone().name().to_string()],
+            )),
+            DateTimeValue::Floating(_) | DateTimeValue::Utc(_) => {}
         }
+        prop
+    }
+
+    /// Creates a property with a date-time value in UTC time.
+    pub fn utc_date_time(name: &'static str, date_time: &DateTime<Utc>) -> Self {
+        Self::date_time_value(name, &DateTimeValue::Utc(*date_time))
+    }
+
+    /// Creates a property with comma-separated date-time values, which
+    /// must be non-empty and share the time zone of the first value.
+    pub fn date_times(name: &'static str, date_times: &[DateTime<Tz>]) -> Self {
+        let time_zone = date_times
+            .first()
+            .expect("property must have >= 1 date-times")
+            .timezone();
+        let values = date_times
+            .iter()
+            .map(|date_time| DateTimeValue::Local(date_time.with_timezone(&time_zone)).into())
+            .collect();
+        let mut prop = Self::with_values(name, values);
+        prop.params
+            .push(Param::new("TZID", vec![time_zone.name().to_string()]));
+        prop
+    }
+
+    /// Returns the name of the property, such as `DTSTART`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the formatted value of the property, where
+    /// multiple values are separated by commas.
+    pub fn value(&self) -> String {
+        self.values.iter().join(",")
+    }
+
+    /// Returns the typed values of the property.
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Returns the comma-separated textual values of the property,
+    /// reverting the escaping of [text values](Value::Text).
+    pub fn texts(&self) -> Vec<String> {
+        parse::split_text(&self.value())
+    }
+
+    /// Searches for a parameter with the given name.
+    pub fn param(&self, name: &str) -> Option<&Param> {
+        self.params.iter().find(|param| param.name == name)
     }
 
     /// Gets a reference to the property parameters.
@@ -195,44 +479,33 @@ impl Prop {
 
 impl Display for Prop {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        // Line folding algorithm
-        const MAX_LINE_LEN: usize = 75; // bytes
-        let mut line_len = 0;
-        let mut write_folded = |part: &str| -> std::fmt::Result {
-            for ch in part.chars() {
-                let ch_len = ch.len_utf8();
-                line_len += ch_len;
-                if line_len > MAX_LINE_LEN {
-                    line_len = 1 + ch_len;
-                    f.write_str("\r\n ")?;
-                }
-                f.write_char(ch)?;
-            }
-            Ok(())
-        };
-
-        write_folded(self.name)?;
-        for param in &self.params {
-            // Property parameters with values containing '.', ';' or
-            // ',' characters must be placed in quoted text. Always
-            // escape to avoid the lookup.
-            let values = param.values.join(r#"",""#);
-            write_folded(&format!(r#";{}="{}""#, param.name, values))?;
-        }
-        write_folded(&format!(":{}\r\n", self.value))
+        Serializer::new(f).prop(self)
+    }
+}
+
+// Values of different types may have the same representation, such as
+// a parsed value and the typed value it was formatted from.
+impl PartialEq for Prop {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.params == other.params
+            && (self.values == other.values
+                || value::same_representation(&self.values, &other.values))
     }
 }
 
+impl Eq for Prop {}
+
 /// A [`Prop`] parameter, containing meta-information about
 /// the property or the property value.
 #[derive(Debug, Eq, PartialEq)]
 pub struct Param {
-    name: &'static str,
+    name: Cow<'static, str>,
     values: Vec<String>,
 }
 
 impl Param {
-    pub fn new(name: &'static str, values: Vec<String>) -> Self {
+    pub fn new<N: Into<Cow<'static, str>>>(name: N, values: Vec<String>) -> Self {
         for value in &values {
             assert!(
                 !value.contains('"'),
@@ -240,64 +513,370 @@ impl Param {
                 value
             );
         }
-        Self { name, values }
+        Self {
+            name: name.into(),
+            values,
+        }
+    }
+
+    /// Returns the name of the parameter, such as `TZID`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the values of the parameter.
+    pub fn values(&self) -> &[String] {
+        &self.values
     }
 }
 
-/// Formats a date with local time according to the RFC 5545
-/// specification.
-pub fn format_date_time(date_time: &DateTime<Tz>) -> String {
-    // The format is loosely based on ISO 8601, but with
-    // dashes (-) and the UTC offset stripped.
-    format!(
-        "{:04}{:02}{:02}T{:02}{:02}{:02}",
-        date_time.year(),
-        date_time.month(),
-        date_time.day(),
-        date_time.hour(),
-        date_time.minute(),
-        date_time.second()
-    )
+/// The CSS3 color names, in alphabetical order.
+static COLOR_NAMES: [&str; 147] = [
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+/// Tests if `color` is a CSS3 color name (ignoring case), such as `turquoise`.
+pub fn is_color_name(color: &str) -> bool {
+    COLOR_NAMES
+        .binary_search(&color.to_ascii_lowercase().as_str())
+        .is_ok()
+}
+
+/// Prefixes the `TZID` parameter values of the given properties
+/// and the properties of every nested component.
+fn globalize_tz_ids(props: &mut [Prop], components: &mut [Component]) {
+    for param in props
+        .iter_mut()
+        .flat_map(|prop| &mut prop.params)
+        .filter(|param| param.name == "TZID")
+    {
+        for value in &mut param.values {
+            if !value.starts_with('/') {
+                value.insert(0, '/');
+            }
+        }
+    }
+    for component in components {
+        globalize_tz_ids(&mut component.props, &mut component.components);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ical::components::{Event, Recurrence, TimeUnit};
-    use crate::ical::{Calendar, Param, Prop};
+    use crate::ical::components::{Alarm, Event, Recurrence, TimeUnit, TimeZone, Trigger};
+    use crate::ical::{
+        is_color_name, Calendar, Component, ComponentHolder, DateTimeValue, Method, Param, Prop,
+        PropHolder,
+    };
     use crate::UC3M_TIMEZONE;
-    use chrono::{Duration, TimeZone, Utc};
+    use chrono::{DateTime, Duration, NaiveDate, Utc};
+    use chrono_tz::America::New_York;
 
     // Calendar
 
     #[test]
     fn single_event() {
-        let date = Utc
-            .ymd(2022, 8, 19)
-            .and_hms(19, 52, 3)
+        let date = "2022-08-19T19:52:03Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
             .with_timezone(&UC3M_TIMEZONE); // 21:52:03 in Madrid
-        let event = Event::new("5678", date.clone(), date);
+        let event = Event::new("5678", date, date);
         let calendar = Calendar::new("test", "2.0", vec![event.into()]);
-        assert_eq!(calendar.to_string(), "BEGIN:VCALENDAR\r\nPRODID:test\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP;TZID=\"/Europe/Madrid\":20220819T215203\r\nUID:5678\r\nDTSTART;TZID=\"/Europe/Madrid\":20220819T215203\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
+        assert_eq!(calendar.to_string(), "BEGIN:VCALENDAR\r\nPRODID:test\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20220819T195203Z\r\nUID:5678\r\nDTSTART;TZID=Europe/Madrid:20220819T215203\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
     }
 
     #[test]
     fn weekly_event() {
-        let last_modified = Utc
-            .ymd(2022, 8, 17)
-            .and_hms(22, 16, 0)
+        let last_modified = "2022-08-17T22:16:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
             .with_timezone(&UC3M_TIMEZONE); // 2022-08-18T00:16:00 in Madrid
-        let first_lecture = Utc
-            .ymd(2022, 9, 12)
-            .and_hms(9, 0, 0)
+        let first_lecture = "2022-09-12T09:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
             .with_timezone(&UC3M_TIMEZONE); // 2022-09-12T11:00:00 in Madrid
-        let event = Event::new("lecture", last_modified.clone(), first_lecture)
+        let event = Event::new("lecture", last_modified, first_lecture)
             .duration(Duration::hours(2))
             .created_on(last_modified)
             .summary("Lecture")
             .location("Room 101")
             .recurrence(Recurrence::times(TimeUnit::Week, 12));
         let calendar = Calendar::new("scheduler", "2.0", vec![event.into()]);
-        assert_eq!(calendar.to_string(), "BEGIN:VCALENDAR\r\nPRODID:scheduler\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP;TZID=\"/Europe/Madrid\":20220818T001600\r\nUID:lecture\r\nDTSTART;TZID=\"/Europe/Madrid\":20220912T110000\r\nCREATED;TZID=\"/Europe/Madrid\":20220818T001600\r\nSUMMARY:Lecture\r\nLOCATION:Room 101\r\nDURATION:PT7200S\r\nRRULE:FREQ=WEEKLY;COUNT=12\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
+        assert_eq!(calendar.to_string(), "BEGIN:VCALENDAR\r\nPRODID:scheduler\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTAMP:20220817T221600Z\r\nUID:lecture\r\nDTSTART;TZID=Europe/Madrid:20220912T110000\r\nCREATED:20220817T221600Z\r\nSUMMARY:Lecture\r\nLOCATION:Room 101\r\nDURATION:PT2H\r\nRRULE:FREQ=WEEKLY;COUNT=12\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n");
+    }
+
+    #[test]
+    fn calendar_name() {
+        let date = "2022-08-19T19:52:03Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("5678", date, date);
+        let calendar = Calendar::new("test", "2.0", vec![event.into()])
+            .name("Lectures")
+            .description("Lectures, labs; exams");
+        assert_eq!(
+            calendar.first_prop("NAME").unwrap().value(),
+            calendar.first_prop("X-WR-CALNAME").unwrap().value()
+        );
+        assert_eq!(
+            calendar.first_prop("DESCRIPTION").unwrap().value(),
+            r"Lectures\, labs\; exams"
+        );
+        assert!(calendar.has_prop("X-WR-CALDESC"));
+    }
+
+    #[test]
+    fn global_time_zones() {
+        let date = "2022-08-19T19:52:03Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("5678", date, date).exclude(date);
+        let calendar = Calendar::new("test", "2.0", vec![event.into()]).global_time_zones();
+        let event = &calendar.components()[0];
+        for name in ["DTSTART", "EXDATE"] {
+            assert_eq!(
+                event.first_prop(name).unwrap().params()[0].values,
+                ["/Europe/Madrid"]
+            );
+        }
+    }
+
+    #[test]
+    fn nested_components() {
+        let mut component = Component::new("VEVENT", vec![Prop::text("UID", &["1"])]);
+        component.components_mut().push(Component::new(
+            "VALARM",
+            vec![Prop::text("ACTION", &["DISPLAY"])],
+        ));
+        assert_eq!(
+            component.to_string(),
+            "BEGIN:VEVENT\r\nUID:1\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nEND:VALARM\r\nEND:VEVENT\r\n"
+        );
+    }
+
+    #[test]
+    fn calendar_props() {
+        let date = "2022-08-19T19:52:03Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("5678", date, date);
+        let calendar = Calendar::new("test", "2.0", vec![event.into()])
+            .method(Method::Publish)
+            .name("Old name")
+            .name("Lectures")
+            .time_zone(UC3M_TIMEZONE)
+            .refresh_interval(Duration::hours(12))
+            .source("https://example.com/calendar.ics?year=2022&group=121")
+            .color("Turquoise");
+        let props: String = calendar.props().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            props,
+            "PRODID:test\r\n\
+            VERSION:2.0\r\n\
+            METHOD:PUBLISH\r\n\
+            NAME:Lectures\r\n\
+            X-WR-CALNAME:Lectures\r\n\
+            X-WR-TIMEZONE:Europe/Madrid\r\n\
+            REFRESH-INTERVAL;VALUE=DURATION:PT12H\r\n\
+            X-PUBLISHED-TTL:PT12H\r\n\
+            SOURCE;VALUE=URI:https://example.com/calendar.ics?year=2022&group=121\r\n\
+            COLOR:turquoise\r\n"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_color() {
+        let date = Utc::now().with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("5678", date, date);
+        Calendar::new("test", "2.0", vec![event.into()]).color("#ff0000");
+    }
+
+    #[test]
+    fn color_names() {
+        assert!(is_color_name("turquoise"));
+        assert!(is_color_name("DarkOrange"));
+        assert!(!is_color_name("bluish"));
+        assert!(!is_color_name(""));
+    }
+
+    #[test]
+    fn component_lookups() {
+        let date = "2022-08-19T19:52:03Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("5678", date, date)
+            .alarm(Alarm::audio(Trigger::Relative(-Duration::minutes(5))));
+        let calendar = Calendar::new("test", "2.0", vec![event.into()]).component(TimeZone::new(
+            UC3M_TIMEZONE,
+            date,
+            date,
+        ));
+        assert!(calendar.has_component("VTIMEZONE"));
+        assert!(!calendar.has_component("VALARM"));
+
+        let event = calendar.first_component("VEVENT").unwrap();
+        let alarm = event.first_component("VALARM").unwrap();
+        assert_eq!(alarm.first_prop("ACTION").unwrap().value(), "AUDIO");
+        let time_zone = calendar.first_component("VTIMEZONE").unwrap();
+        assert!(time_zone.has_component("DAYLIGHT"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn calendar_sub_component() {
+        let alarm = Alarm::display(Trigger::Relative(Duration::zero()), "Now");
+        Calendar::new("test", "2.0", vec![alarm.into()]);
     }
 
     #[test]
@@ -311,17 +890,17 @@ mod tests {
     #[test]
     fn prop_display() {
         assert_eq!(
-            Prop::new("TITLE", "Hello world!").to_string(),
+            Prop::text("TITLE", &["Hello world!"]).to_string(),
             "TITLE:Hello world!\r\n"
         );
 
-        let mut prop = Prop::new("TABLE", "The value.");
+        let mut prop = Prop::text("TABLE", &["The value."]);
         prop.params_mut().push(Param::new("ROW", vec![";,".into()]));
         assert_eq!(prop.to_string(), "TABLE;ROW=\";,\":The value.\r\n");
 
-        let mut prop = Prop::new("NAME", "Something.");
+        let mut prop = Prop::text("NAME", &["Something."]);
         prop.params_mut().extend([
-            Param::new("FOO", vec!["bar".into(), "baz".into()]),
+            Param::new("FOO", vec!["bar".into(), "mailto:baz".into()]),
             Param::new(
                 "ANOTHER",
                 vec!["hello".into(), "beautiful".into(), "world".into()],
@@ -329,10 +908,45 @@ mod tests {
         ]);
         assert_eq!(
             prop.to_string(),
-            "NAME;FOO=\"bar\",\"baz\";ANOTHER=\"hello\",\"beautiful\",\"world\":Something.\r\n"
+            "NAME;FOO=bar,\"mailto:baz\";ANOTHER=hello,beautiful,world:Something.\r\n"
+        );
+
+        assert_eq!(Prop::text("DESCRIPTION", &["This is a long description that exists on multiple long lines since this is a very long string that exceeds the maximum number of bytes allowed by the iCalendar specification published in the Request for Comments 5545 in September 2009."]).to_string(), "DESCRIPTION:This is a long description that exists on multiple long lines s\r\n ince this is a very long string that exceeds the maximum number of bytes a\r\n llowed by the iCalendar specification published in the Request for Comment\r\n s 5545 in September 2009.\r\n");
+    }
+
+    #[test]
+    fn date_time_values() {
+        // Examples from sections 3.3.4 and 3.3.5 of RFC 5545.
+        let date = NaiveDate::from_ymd_opt(1997, 7, 14).unwrap();
+        assert_eq!(
+            Prop::date_time_value("DTSTART", &DateTimeValue::Date(date)).to_string(),
+            "DTSTART;VALUE=DATE:19970714\r\n"
         );
 
-        assert_eq!(Prop::new("DESCRIPTION", "This is a long description that exists on multiple long lines since this is a very long string that exceeds the maximum number of bytes allowed by the iCalendar specification published in the Request for Comments 5545 in September 2009.").to_string(), "DESCRIPTION:This is a long description that exists on multiple long lines s\r\n ince this is a very long string that exceeds the maximum number of bytes a\r\n llowed by the iCalendar specification published in the Request for Comment\r\n s 5545 in September 2009.\r\n");
+        let floating = NaiveDate::from_ymd_opt(1998, 1, 18)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap();
+        assert_eq!(
+            Prop::date_time_value("DTSTART", &DateTimeValue::Floating(floating)).to_string(),
+            "DTSTART:19980118T230000\r\n"
+        );
+
+        let utc = "1998-01-19T07:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            Prop::utc_date_time("DTSTART", &utc).to_string(),
+            "DTSTART:19980119T070000Z\r\n"
+        );
+
+        let local = utc.with_timezone(&New_York);
+        assert_eq!(
+            Prop::date_time_value("DTSTART", &DateTimeValue::Local(local)).to_string(),
+            "DTSTART;TZID=America/New_York:19980119T020000\r\n"
+        );
+        assert_eq!(
+            Prop::date_time("DTSTART", &local),
+            Prop::date_time_value("DTSTART", &DateTimeValue::Local(local))
+        );
     }
 
     #[test]
@@ -0,0 +1,436 @@
+use crate::ical::{Calendar, Component, Param, Prop, Value, SUB_COMPONENTS};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+impl FromStr for Calendar {
+    type Err = ParseCalendarError;
+
+    /// Parses an iCalendar object, which must contain a single
+    /// `VCALENDAR` component with at least one nested component.
+    /// Components such as `VALARM` cannot be nested in it directly.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The components that have begun but not ended yet.
+        let mut open: Vec<(usize, Component)> = Vec::new();
+        let mut calendar = None;
+        let mut last_line = 0;
+        for (line, content) in unfold(s) {
+            last_line = line;
+            if content.is_empty() {
+                continue;
+            }
+            let error = |kind| ParseCalendarError::new(line, kind);
+            if calendar.is_some() {
+                return Err(error(ParseCalendarErrorKind::TrailingContent));
+            }
+            let prop = parse_content_line(content).map_err(error)?;
+            if prop.name == "BEGIN" {
+                let name = prop.value().to_ascii_uppercase();
+                open.push((line, Component::new(name, Vec::new())));
+            } else if prop.name == "END" {
+                let (begin_line, component) = open
+                    .pop()
+                    .filter(|(_, component)| component.name.eq_ignore_ascii_case(&prop.value()))
+                    .ok_or_else(|| error(ParseCalendarErrorKind::UnexpectedEnd(prop.value())))?;
+                if open.len() == 1 && SUB_COMPONENTS.contains(&component.name()) {
+                    let kind = ParseCalendarErrorKind::MisplacedComponent(component.name.into());
+                    return Err(ParseCalendarError::new(begin_line, kind));
+                }
+                match open.last_mut() {
+                    Some((_, parent)) => parent.components.push(component),
+                    None => calendar = Some(component),
+                }
+            } else {
+                let (_, component) = open
+                    .last_mut()
+                    .ok_or_else(|| error(ParseCalendarErrorKind::PropOutsideComponent))?;
+                component.props.push(prop);
+            }
+        }
+        if let Some((line, component)) = open.pop() {
+            let kind = ParseCalendarErrorKind::UnclosedComponent(component.name.into_owned());
+            return Err(ParseCalendarError::new(line, kind));
+        }
+        let error = |kind| ParseCalendarError::new(last_line, kind);
+        let calendar = calendar
+            .filter(|calendar| calendar.name == "VCALENDAR")
+            .ok_or_else(|| error(ParseCalendarErrorKind::MissingCalendar))?;
+        if calendar.components.is_empty() {
+            return Err(error(ParseCalendarErrorKind::MissingComponents));
+        }
+        Ok(Self {
+            props: calendar.props,
+            components: calendar.components,
+        })
+    }
+}
+
+/// The properties whose values are always text, which are unescaped
+/// while parsing. The values of other properties are kept as is.
+const TEXT_PROPS: [&str; 22] = [
+    "ACTION",
+    "CATEGORIES",
+    "CLASS",
+    "COLOR",
+    "COMMENT",
+    "CONTACT",
+    "DESCRIPTION",
+    "LOCATION",
+    "METHOD",
+    "NAME",
+    "PRODID",
+    "RESOURCES",
+    "STATUS",
+    "SUMMARY",
+    "TRANSP",
+    "TZID",
+    "TZNAME",
+    "UID",
+    "VERSION",
+    "X-WR-CALDESC",
+    "X-WR-CALNAME",
+    "X-WR-TIMEZONE",
+];
+
+/// Joins the folded lines of `s`, returning each content line
+/// along with its line number, starting from 1.
+fn unfold(s: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, line) in s.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, content))) => content.push_str(continuation),
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+/// Parses a content line of the form `name *(";" param) ":" value`.
+fn parse_content_line(line: String) -> Result<Prop, ParseCalendarErrorKind> {
+    let name_end = line
+        .find([';', ':'])
+        .ok_or(ParseCalendarErrorKind::MissingValue)?;
+    let mut prop = Prop::with_values(parse_name(&line[..name_end])?, Vec::new());
+    let mut rest = &line[name_end..];
+    while let Some(param) = rest.strip_prefix(';') {
+        let (name, mut values) = param
+            .split_once('=')
+            .filter(|(name, _)| !name.contains([';', ':']))
+            .ok_or(ParseCalendarErrorKind::MissingParamValue)?;
+        let mut param = Param::new(parse_name(name)?, Vec::new());
+        loop {
+            let (value, remaining) = match values.strip_prefix('"') {
+                Some(quoted) => {
+                    let end = quoted
+                        .find('"')
+                        .ok_or(ParseCalendarErrorKind::UnterminatedQuote)?;
+                    (&quoted[..end], &quoted[end + 1..])
+                }
+                None => values.split_at(values.find([',', ';', ':']).unwrap_or(values.len())),
+            };
+            param.values.push(value.to_string());
+            match remaining.strip_prefix(',') {
+                Some(remaining) => values = remaining,
+                None => {
+                    rest = remaining;
+                    break;
+                }
+            }
+        }
+        prop.params.push(param);
+    }
+    let value = rest
+        .strip_prefix(':')
+        .ok_or(ParseCalendarErrorKind::MissingValue)?;
+    prop.values = if TEXT_PROPS.contains(&&*prop.name) {
+        split_text(value).into_iter().map(Value::Text).collect()
+    } else {
+        vec![Value::Raw(value.to_string())]
+    };
+    Ok(prop)
+}
+
+/// Parses a property or parameter name, which is case-insensitive.
+fn parse_name(name: &str) -> Result<String, ParseCalendarErrorKind> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+    {
+        return Err(ParseCalendarErrorKind::InvalidName(name.to_string()));
+    }
+    Ok(name.to_ascii_uppercase())
+}
+
+/// Splits a list of comma-separated textual values and unescapes them,
+/// according to section 3.3.11.
+pub(crate) fn split_text(value: &str) -> Vec<String> {
+    let mut values = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        let current = values.last_mut().unwrap();
+        match ch {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => current.push('\n'),
+                Some(escaped) => current.push(escaped),
+                None => current.push('\\'),
+            },
+            ',' => values.push(String::new()),
+            _ => current.push(ch),
+        }
+    }
+    values
+}
+
+/// The error returned when an iCalendar object is malformed.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ParseCalendarError {
+    line: usize,
+    kind: ParseCalendarErrorKind,
+}
+
+impl ParseCalendarError {
+    fn new(line: usize, kind: ParseCalendarErrorKind) -> Self {
+        Self { line, kind }
+    }
+
+    /// Returns the number of the malformed line, starting from 1.
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the kind of error.
+    pub const fn kind(&self) -> &ParseCalendarErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for ParseCalendarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cannot parse calendar at line {}: {}",
+            self.line, self.kind
+        )
+    }
+}
+
+impl Error for ParseCalendarError {}
+
+/// The kinds of errors that can occur when parsing an iCalendar object.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ParseCalendarErrorKind {
+    /// The name of a property or parameter contains invalid characters.
+    InvalidName(String),
+    /// A content line doesn't have a value separated by a colon.
+    MissingValue,
+    /// A parameter doesn't have a value separated by an equals sign.
+    MissingParamValue,
+    /// A quoted parameter value doesn't have a closing quote.
+    UnterminatedQuote,
+    /// A property appears outside of any component.
+    PropOutsideComponent,
+    /// A component ends without having begun.
+    UnexpectedEnd(String),
+    /// A component begins but doesn't end.
+    UnclosedComponent(String),
+    /// A component that must be nested within another component
+    /// is placed directly within the calendar.
+    MisplacedComponent(String),
+    /// The object isn't a `VCALENDAR` component.
+    MissingCalendar,
+    /// The calendar doesn't have any components.
+    MissingComponents,
+    /// The calendar is followed by other content lines.
+    TrailingContent,
+}
+
+impl Display for ParseCalendarErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseCalendarErrorKind::InvalidName(name) => write!(f, "invalid name `{}`", name),
+            ParseCalendarErrorKind::MissingValue => f.write_str("missing property value"),
+            ParseCalendarErrorKind::MissingParamValue => f.write_str("missing parameter value"),
+            ParseCalendarErrorKind::UnterminatedQuote => {
+                f.write_str("unterminated quoted parameter value")
+            }
+            ParseCalendarErrorKind::PropOutsideComponent => {
+                f.write_str("property outside of a component")
+            }
+            ParseCalendarErrorKind::UnexpectedEnd(name) => {
+                write!(f, "unexpected end of component `{}`", name)
+            }
+            ParseCalendarErrorKind::UnclosedComponent(name) => {
+                write!(f, "component `{}` is never closed", name)
+            }
+            ParseCalendarErrorKind::MisplacedComponent(name) => {
+                write!(
+                    f,
+                    "component `{}` must be nested within another component",
+                    name
+                )
+            }
+            ParseCalendarErrorKind::MissingCalendar => {
+                f.write_str("expected a VCALENDAR component")
+            }
+            ParseCalendarErrorKind::MissingComponents => {
+                f.write_str("calendar must have >= 1 components")
+            }
+            ParseCalendarErrorKind::TrailingContent => {
+                f.write_str("unexpected content after the calendar")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ical::components::{Event, Recurrence, TimeUnit, TimeZone};
+    use crate::ical::parse::split_text;
+    use crate::ical::{Calendar, ComponentHolder, ParseCalendarErrorKind, Prop, PropHolder, Value};
+    use crate::UC3M_TIMEZONE;
+    use chrono::{DateTime, Duration, Utc};
+
+    #[test]
+    fn parse_calendar() {
+        let calendar = "BEGIN:VCALENDAR\r\n\
+            PRODID:test\r\n\
+            VERSION:2.0\r\n\
+            begin:vevent\r\n\
+            UID:1234\r\n\
+            DESCRIPTION:A very long description that has been folded\r\n  over two lines\r\n\
+            CATEGORIES:Meeting,Work\\, urgent\\nor not\r\n\
+            ATTENDEE;ROLE=CHAIR;DELEGATED-FROM=\"mailto:a@example.com\",\"mailto:b@example.com\":mailto:c@example.com\r\n\
+            BEGIN:VALARM\r\n\
+            ACTION:DISPLAY\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n"
+            .parse::<Calendar>()
+            .unwrap();
+        assert_eq!(calendar.first_prop("PRODID").unwrap().value(), "test");
+
+        let event = &calendar.components()[0];
+        assert_eq!(event.name(), "VEVENT");
+        assert_eq!(
+            event.first_prop("DESCRIPTION").unwrap().value(),
+            "A very long description that has been folded over two lines"
+        );
+        assert_eq!(
+            event.first_prop("CATEGORIES").unwrap().texts(),
+            ["Meeting", "Work, urgent\nor not"]
+        );
+        assert_eq!(
+            event.first_prop("CATEGORIES").unwrap().values()[1],
+            Value::Text("Work, urgent\nor not".to_string())
+        );
+
+        let attendee = event.first_prop("ATTENDEE").unwrap();
+        assert_eq!(attendee.value(), "mailto:c@example.com");
+        assert_eq!(
+            attendee.values(),
+            [Value::Raw("mailto:c@example.com".to_string())]
+        );
+        assert_eq!(attendee.param("ROLE").unwrap().values(), ["CHAIR"]);
+        assert_eq!(
+            attendee.param("DELEGATED-FROM").unwrap().values(),
+            ["mailto:a@example.com", "mailto:b@example.com"]
+        );
+
+        let alarm = &event.components()[0];
+        assert_eq!(alarm.name(), "VALARM");
+        assert_eq!(alarm.first_prop("ACTION").unwrap().value(), "DISPLAY");
+    }
+
+    #[test]
+    fn round_trip() {
+        let start = "2022-09-12T09:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let event = Event::new("lecture", start, start)
+            .duration(Duration::hours(2))
+            .summary("Lecture; room 101, \"main\" building")
+            .recurrence(Recurrence::until(
+                TimeUnit::Week,
+                start + Duration::weeks(8),
+            ))
+            .exclude(start + Duration::weeks(1));
+        let time_zone = TimeZone::new(UC3M_TIMEZONE, start, start + Duration::weeks(8));
+        let calendar = Calendar::new("test", "2.0", vec![time_zone.into(), event.into()])
+            .description("A description that is long enough to be folded into multiple lines.");
+
+        let parsed = calendar.to_string().parse::<Calendar>().unwrap();
+        assert_eq!(parsed, calendar);
+        assert_eq!(parsed.to_string(), calendar.to_string());
+    }
+
+    #[test]
+    fn malformed_calendars() {
+        let error = |calendar: &str| calendar.parse::<Calendar>().unwrap_err();
+
+        let err = error("BEGIN:VCALENDAR\r\nPRODID\r\nEND:VCALENDAR\r\n");
+        assert_eq!(err.line(), 2);
+        assert_eq!(err.kind(), &ParseCalendarErrorKind::MissingValue);
+
+        let err = error("BEGIN:VCALENDAR\r\nX-TEST;A=\"b:c\r\nEND:VCALENDAR\r\n");
+        assert_eq!(err.kind(), &ParseCalendarErrorKind::UnterminatedQuote);
+
+        let err = error("BEGIN:VCALENDAR\r\nX TEST:value\r\nEND:VCALENDAR\r\n");
+        assert_eq!(
+            err.kind(),
+            &ParseCalendarErrorKind::InvalidName("X TEST".to_string())
+        );
+
+        let err = error("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VTODO\r\n");
+        assert_eq!(err.line(), 3);
+        assert_eq!(
+            err.kind(),
+            &ParseCalendarErrorKind::UnexpectedEnd("VTODO".to_string())
+        );
+
+        let err = error("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VEVENT\r\n");
+        assert_eq!(err.line(), 1);
+        assert_eq!(
+            err.kind(),
+            &ParseCalendarErrorKind::UnclosedComponent("VCALENDAR".to_string())
+        );
+
+        let err = error("BEGIN:VCALENDAR\r\nBEGIN:VALARM\r\nEND:VALARM\r\nEND:VCALENDAR\r\n");
+        assert_eq!(err.line(), 2);
+        assert_eq!(
+            err.kind(),
+            &ParseCalendarErrorKind::MisplacedComponent("VALARM".to_string())
+        );
+        let err = error("BEGIN:VCALENDAR\r\nBEGIN:standard\r\nEND:standard\r\nEND:VCALENDAR\r\n");
+        assert_eq!(
+            err.kind(),
+            &ParseCalendarErrorKind::MisplacedComponent("STANDARD".to_string())
+        );
+
+        let err = error("UID:1234\r\n");
+        assert_eq!(err.kind(), &ParseCalendarErrorKind::PropOutsideComponent);
+
+        let err = error("BEGIN:VEVENT\r\nEND:VEVENT\r\n");
+        assert_eq!(err.kind(), &ParseCalendarErrorKind::MissingCalendar);
+
+        let err = error("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n");
+        assert_eq!(err.kind(), &ParseCalendarErrorKind::MissingComponents);
+
+        let err =
+            error("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VEVENT\r\nEND:VCALENDAR\r\nUID:1\r\n");
+        assert_eq!(err.line(), 5);
+        assert_eq!(err.kind(), &ParseCalendarErrorKind::TrailingContent);
+    }
+
+    #[test]
+    fn unescape_text() {
+        assert_eq!(split_text(""), [""]);
+        assert_eq!(split_text(r"a\\b\;c\,d\Ne"), ["a\\b;c,d\ne"]);
+        assert_eq!(split_text("a,b,"), ["a", "b", ""]);
+
+        let values = ["Meeting", "Work, urgent; \\ or\nnot"];
+        assert_eq!(Prop::text("CATEGORIES", &values).texts(), values);
+    }
+}
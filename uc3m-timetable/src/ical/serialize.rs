@@ -0,0 +1,273 @@
+use crate::ical::{Calendar, Component, Prop};
+use std::borrow::Borrow;
+use std::fmt::{Result, Write};
+
+/// Writes calendar objects as folded content lines into a [`Write`]r,
+/// without building intermediate strings.
+///
+/// To write into an [`io::Write`](std::io::Write)r instead, see
+/// [`Calendar::write_to`].
+#[derive(Debug)]
+pub struct Serializer<W> {
+    writer: W,
+}
+
+impl<W: Write> Serializer<W> {
+    /// Creates a serializer that writes into `writer`.
+    pub const fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes a calendar object.
+    pub fn calendar(&mut self, calendar: &Calendar) -> Result {
+        self.calendar_start(calendar)?;
+        for component in &calendar.components {
+            self.component(component)?;
+        }
+        self.end("VCALENDAR")
+    }
+
+    /// Writes a component, including its nested components.
+    pub fn component(&mut self, component: &Component) -> Result {
+        self.begin(&component.name)?;
+        for prop in &component.props {
+            self.prop(prop)?;
+        }
+        for component in &component.components {
+            self.component(component)?;
+        }
+        self.end(&component.name)
+    }
+
+    /// Writes a property, folding its content line if it is longer
+    /// than 75 bytes.
+    pub fn prop(&mut self, prop: &Prop) -> Result {
+        let mut line = FoldedLine {
+            writer: &mut self.writer,
+            len: 0,
+        };
+        line.write_str(&prop.name)?;
+        for param in &prop.params {
+            write!(line, ";{}=", param.name)?;
+            for (index, value) in param.values.iter().enumerate() {
+                if index > 0 {
+                    line.write_char(',')?;
+                }
+                // Property parameters with values containing ':', ';' or
+                // ',' characters must be placed in quoted text.
+                if value.contains([':', ';', ',']) {
+                    write!(line, "\"{}\"", value)?;
+                } else {
+                    line.write_str(value)?;
+                }
+            }
+        }
+        line.write_char(':')?;
+        for (index, value) in prop.values.iter().enumerate() {
+            if index > 0 {
+                line.write_char(',')?;
+            }
+            write!(line, "{}", value)?;
+        }
+        self.writer.write_str("\r\n")
+    }
+
+    /// Writes the start of a calendar object, along with its properties.
+    fn calendar_start(&mut self, calendar: &Calendar) -> Result {
+        self.begin("VCALENDAR")?;
+        for prop in &calendar.props {
+            self.prop(prop)?;
+        }
+        Ok(())
+    }
+
+    fn begin(&mut self, name: &str) -> Result {
+        write!(self.writer, "BEGIN:{}\r\n", name)
+    }
+
+    fn end(&mut self, name: &str) -> Result {
+        write!(self.writer, "END:{}\r\n", name)
+    }
+}
+
+/// A [`Write`]r that folds the content line written into it.
+struct FoldedLine<'a, W> {
+    writer: &'a mut W,
+    /// The length of the current line, in bytes.
+    len: usize,
+}
+
+impl<W: Write> Write for FoldedLine<'_, W> {
+    fn write_str(&mut self, mut s: &str) -> Result {
+        const MAX_LINE_LEN: usize = 75; // bytes
+        while !s.is_empty() {
+            // Write the longest prefix that fits within the line,
+            // without splitting a multi-byte character.
+            let mut end = s.len().min(MAX_LINE_LEN - self.len);
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            if end == 0 {
+                self.writer.write_str("\r\n ")?;
+                self.len = 1;
+                continue;
+            }
+            self.writer.write_str(&s[..end])?;
+            self.len += end;
+            s = &s[end..];
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the serialized chunks of a [`Calendar`].
+///
+/// The first chunk contains the start of the calendar object and
+/// its properties, each of the following chunks contains a top-level
+/// component, and the last chunk contains the end of the object.
+#[derive(Debug)]
+pub struct Chunks<C> {
+    calendar: C,
+    position: usize,
+}
+
+impl<C: Borrow<Calendar>> Chunks<C> {
+    pub(crate) const fn new(calendar: C) -> Self {
+        Self {
+            calendar,
+            position: 0,
+        }
+    }
+}
+
+impl<C: Borrow<Calendar>> Iterator for Chunks<C> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let calendar = self.calendar.borrow();
+        let components = &calendar.components;
+        let mut serializer = Serializer::new(String::new());
+        let result = match self.position {
+            0 => serializer.calendar_start(calendar),
+            position if position <= components.len() => {
+                serializer.component(&components[position - 1])
+            }
+            position if position == components.len() + 1 => serializer.end("VCALENDAR"),
+            _ => return None,
+        };
+        result.expect("a Display implementation returned an error unexpectedly");
+        self.position += 1;
+        Some(serializer.into_inner())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.calendar.borrow().components.len() + 2).saturating_sub(self.position);
+        (len, Some(len))
+    }
+}
+
+impl<C: Borrow<Calendar>> ExactSizeIterator for Chunks<C> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::ical::components::{Event, TimeZone};
+    use crate::ical::{Calendar, Prop, Serializer};
+    use crate::UC3M_TIMEZONE;
+    use chrono::{DateTime, Duration, Utc};
+    use std::io;
+
+    fn calendar() -> Calendar {
+        let start = "2022-09-12T09:00:00Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&UC3M_TIMEZONE);
+        let events = (0..3).map(|index| {
+            Event::new(index.to_string(), start, start + Duration::days(index))
+                .summary("Lecture, room 101")
+                .duration(Duration::hours(2))
+        });
+        let time_zone = TimeZone::new(UC3M_TIMEZONE, start, start + Duration::days(3));
+        let components = std::iter::once(time_zone.into())
+            .chain(events.map(Into::into))
+            .collect();
+        Calendar::new("test", "2.0", components)
+            .description("A description that is long enough to be folded into multiple lines.")
+    }
+
+    #[test]
+    fn serialize_calendar() {
+        let calendar = calendar();
+        let mut serializer = Serializer::new(String::new());
+        serializer.calendar(&calendar).unwrap();
+        assert_eq!(serializer.into_inner(), calendar.to_string());
+
+        let mut bytes = Vec::new();
+        calendar.write_to(&mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), calendar.to_string());
+    }
+
+    #[test]
+    fn buffered_writes() {
+        /// An unbuffered writer that counts the writes it receives.
+        struct Counter(usize);
+
+        impl io::Write for Counter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0 += 1;
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let calendar = calendar();
+        let mut counter = Counter(0);
+        calendar.write_to(&mut counter).unwrap();
+        assert_eq!(counter.0, 1);
+    }
+
+    #[test]
+    fn fold_multi_byte_chars() {
+        // The 75th byte falls within the two bytes of the last 'á'.
+        let value = "á".repeat(40);
+        let mut serializer = Serializer::new(String::new());
+        serializer.prop(&Prop::text("SUMMARY", &[value])).unwrap();
+        assert_eq!(
+            serializer.into_inner(),
+            format!("SUMMARY:{}\r\n {}\r\n", "á".repeat(33), "á".repeat(7))
+        );
+
+        // Lines of exactly 75 bytes are not folded.
+        let mut serializer = Serializer::new(String::new());
+        serializer
+            .prop(&Prop::text("SUMMARY", &["a".repeat(67)]))
+            .unwrap();
+        assert_eq!(
+            serializer.into_inner(),
+            format!("SUMMARY:{}\r\n", "a".repeat(67))
+        );
+    }
+
+    #[test]
+    fn calendar_chunks() {
+        let calendar = calendar();
+        let chunks = calendar.chunks();
+        assert_eq!(chunks.len(), 6);
+
+        let chunks = chunks.collect::<Vec<_>>();
+        assert!(chunks[0].starts_with("BEGIN:VCALENDAR\r\nPRODID:test\r\n"));
+        assert!(chunks[1].starts_with("BEGIN:VTIMEZONE\r\n"));
+        assert!(chunks[4].starts_with("BEGIN:VEVENT\r\nDTSTAMP:"));
+        assert_eq!(chunks[5], "END:VCALENDAR\r\n");
+        let serialized = calendar.to_string();
+        assert_eq!(chunks.concat(), serialized);
+        assert_eq!(calendar.into_chunks().collect::<String>(), serialized);
+    }
+}
@@ -0,0 +1,324 @@
+use crate::ical::components::{Geo, Recurrence};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use itertools::Itertools;
+use std::fmt::{Display, Formatter, Write};
+
+/// A typed property value, as defined in section 3.3 of RFC 5545.
+///
+/// Each value is formatted according to its type, so textual values
+/// are always escaped when the [`Prop`](crate::ical::Prop) holding
+/// them is written.
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    /// Human-readable text, such as `Project XYZ Final Review`.
+    Text(String),
+    /// A calendar date, such as `19970714`.
+    Date(NaiveDate),
+    /// A date with time, such as `19980119T070000Z`.
+    DateTime(DateTimeValue),
+    /// A duration of time, such as `P15DT5H0M20S`.
+    Duration(Duration),
+    /// A recurrence rule, such as `FREQ=WEEKLY;COUNT=10`.
+    Recur(Box<Recurrence>),
+    /// A uniform resource identifier, such as `http://example.com/`.
+    Uri(String),
+    /// A signed integer, such as `1234567890`.
+    Integer(i32),
+    /// A real number, such as `1000000.0000001`.
+    Float(f64),
+    /// The address of a calendar user, such as `mailto:jane_doe@example.com`.
+    CalAddress(String),
+    /// An offset from UTC in seconds, formatted as `-0500`.
+    UtcOffset(i32),
+    /// A global position, formatted as `37.386013;-122.082932`.
+    Geo(Geo),
+    /// A value that is already formatted, such as the value of
+    /// a parsed property whose type isn't known.
+    Raw(String),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Text(text) => {
+                // Escape text according to section 3.3.11.
+                for ch in text.chars() {
+                    match ch {
+                        '\\' | ';' | ',' => {
+                            f.write_char('\\')?;
+                            f.write_char(ch)?;
+                        }
+                        '\n' => f.write_str(r"\n")?,
+                        _ => f.write_char(ch)?,
+                    }
+                }
+                Ok(())
+            }
+            Value::Date(date) => write!(f, "{}", date.format("%Y%m%d")),
+            Value::DateTime(date_time) => date_time.fmt(f),
+            Value::Duration(duration) => f.write_str(&format_duration(*duration)),
+            Value::Recur(recurrence) => recurrence.fmt(f),
+            Value::Integer(integer) => integer.fmt(f),
+            Value::Float(float) => float.fmt(f),
+            Value::UtcOffset(seconds) => f.write_str(&format_utc_offset(*seconds)),
+            Value::Geo(geo) => geo.fmt(f),
+            Value::Uri(value) | Value::CalAddress(value) | Value::Raw(value) => f.write_str(value),
+        }
+    }
+}
+
+impl From<NaiveDate> for Value {
+    fn from(date: NaiveDate) -> Self {
+        Value::Date(date)
+    }
+}
+
+impl From<DateTimeValue> for Value {
+    fn from(date_time: DateTimeValue) -> Self {
+        Value::DateTime(date_time)
+    }
+}
+
+impl From<Duration> for Value {
+    fn from(duration: Duration) -> Self {
+        Value::Duration(duration)
+    }
+}
+
+impl From<Recurrence> for Value {
+    fn from(recurrence: Recurrence) -> Self {
+        Value::Recur(Box::new(recurrence))
+    }
+}
+
+impl From<i32> for Value {
+    fn from(integer: i32) -> Self {
+        Value::Integer(integer)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(float: f64) -> Self {
+        assert!(
+            float.is_finite(),
+            "float value must be finite; got {}",
+            float
+        );
+        Value::Float(float)
+    }
+}
+
+impl From<Geo> for Value {
+    fn from(geo: Geo) -> Self {
+        Value::Geo(geo)
+    }
+}
+
+/// A date or date-time property value, as defined in sections
+/// 3.3.4 and 3.3.5 of RFC 5545.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum DateTimeValue {
+    /// A calendar date, such as `19970714`.
+    Date(NaiveDate),
+    /// A date with local time that isn't bound to any time zone,
+    /// such as `19980118T230000`.
+    Floating(NaiveDateTime),
+    /// A date with local time in the time zone given by the `TZID`
+    /// parameter of the property, such as `19980119T020000`.
+    Local(DateTime<Tz>),
+    /// A date with UTC time, such as `19980119T070000Z`.
+    Utc(DateTime<Utc>),
+}
+
+impl Display for DateTimeValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The format is loosely based on ISO 8601, but with
+        // dashes (-) and the UTC offset stripped.
+        const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+        match self {
+            DateTimeValue::Date(date) => write!(f, "{}", date.format("%Y%m%d")),
+            DateTimeValue::Floating(date_time) => {
+                write!(f, "{}", date_time.format(DATE_TIME_FORMAT))
+            }
+            DateTimeValue::Local(date_time) => write!(f, "{}", date_time.format(DATE_TIME_FORMAT)),
+            DateTimeValue::Utc(date_time) => {
+                write!(f, "{}Z", date_time.format(DATE_TIME_FORMAT))
+            }
+        }
+    }
+}
+
+/// Tests if both lists of comma-separated values are formatted identically.
+/// Parsed properties hold a single raw value, which is compared without
+/// formatting the other values into a string.
+pub(crate) fn same_representation(values: &[Value], other: &[Value]) -> bool {
+    match (values, other) {
+        ([Value::Raw(raw)], values) | (values, [Value::Raw(raw)]) => formats_as(values, raw),
+        _ => formats_as(values, &other.iter().join(",")),
+    }
+}
+
+/// Tests if the comma-separated `values` are formatted as `expected`.
+fn formats_as(values: &[Value], expected: &str) -> bool {
+    /// A [`Write`]r that fails as soon as the output differs.
+    struct Matcher<'a> {
+        remaining: &'a str,
+    }
+
+    impl Write for Matcher<'_> {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            self.remaining = self.remaining.strip_prefix(s).ok_or(std::fmt::Error)?;
+            Ok(())
+        }
+    }
+
+    let mut matcher = Matcher {
+        remaining: expected,
+    };
+    let result = values.iter().enumerate().try_for_each(|(index, value)| {
+        if index > 0 {
+            matcher.write_char(',')?;
+        }
+        write!(matcher, "{}", value)
+    });
+    result.is_ok() && matcher.remaining.is_empty()
+}
+
+/// Formats a duration according to section 3.3.6 of RFC 5545,
+/// such as `-PT15M` or `P1DT12H`.
+fn format_duration(duration: Duration) -> String {
+    let sign = if duration < Duration::zero() { "-" } else { "" };
+    let seconds = duration.num_seconds().abs();
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+    let mut formatted = format!("{}P", sign);
+    if days > 0 {
+        formatted.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || days == 0 {
+        formatted.push('T');
+        if hours > 0 {
+            formatted.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 {
+            formatted.push_str(&format!("{}M", minutes));
+        }
+        if seconds > 0 || (hours == 0 && minutes == 0) {
+            formatted.push_str(&format!("{}S", seconds));
+        }
+    }
+    formatted
+}
+
+/// Formats a UTC offset given in seconds as `+hhmm`, or as `+hhmmss`
+/// if it includes seconds.
+fn format_utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+    match seconds % 60 {
+        0 => format!("{}{:02}{:02}", sign, hours, minutes),
+        seconds => format!("{}{:02}{:02}{:02}", sign, hours, minutes, seconds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ical::components::{Recurrence, TimeUnit};
+    use crate::ical::value::{same_representation, DateTimeValue, Value};
+    use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+    #[test]
+    fn text_values() {
+        let text = |text: &str| Value::Text(text.to_string());
+        assert_eq!(text("Hello world!").to_string(), "Hello world!");
+        assert_eq!(
+            text("ANÁLISIS FUNCIONAL APLICADO, grp.121; room\\1\nA").to_string(),
+            r"ANÁLISIS FUNCIONAL APLICADO\, grp.121\; room\\1\nA"
+        );
+        // Other value types are never escaped.
+        assert_eq!(
+            Value::Uri("http://example.com/a,b;c".to_string()).to_string(),
+            "http://example.com/a,b;c"
+        );
+        assert_eq!(
+            Value::CalAddress("mailto:jane_doe@example.com".to_string()).to_string(),
+            "mailto:jane_doe@example.com"
+        );
+    }
+
+    #[test]
+    fn typed_values() {
+        // Examples from section 3.3 of RFC 5545.
+        let date = NaiveDate::from_ymd_opt(1997, 7, 14).unwrap();
+        assert_eq!(Value::from(date).to_string(), "19970714");
+
+        let utc = "1998-01-19T07:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            Value::from(DateTimeValue::Utc(utc)).to_string(),
+            "19980119T070000Z"
+        );
+        assert_eq!(
+            Value::from(Recurrence::times(TimeUnit::Week, 10)).to_string(),
+            "FREQ=WEEKLY;COUNT=10"
+        );
+        assert_eq!(Value::from(-1234567890).to_string(), "-1234567890");
+        assert_eq!(Value::from(1000000.0000001).to_string(), "1000000.0000001");
+        assert_eq!(Value::from(-1.5).to_string(), "-1.5");
+    }
+
+    #[test]
+    #[should_panic]
+    fn non_finite_float() {
+        let _ = Value::from(f64::NAN);
+    }
+
+    #[test]
+    fn same_representations() {
+        let raw = |value: &str| [Value::Raw(value.to_string())];
+        let utc = "1998-01-19T07:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let values = [
+            Value::DateTime(DateTimeValue::Utc(utc)),
+            Value::Duration(Duration::hours(2)),
+        ];
+        assert!(same_representation(&values, &raw("19980119T070000Z,PT2H")));
+        assert!(same_representation(&raw("19980119T070000Z,PT2H"), &values));
+        assert!(!same_representation(&values, &raw("19980119T070000Z,PT2")));
+        assert!(!same_representation(
+            &values,
+            &raw("19980119T070000Z,PT2H,")
+        ));
+
+        let date = NaiveDate::from_ymd_opt(1997, 7, 14).unwrap();
+        assert!(same_representation(
+            &[Value::Date(date)],
+            &[Value::DateTime(DateTimeValue::Date(date))]
+        ));
+    }
+
+    #[test]
+    fn durations() {
+        let format = |duration| Value::Duration(duration).to_string();
+        assert_eq!(format(-Duration::minutes(15)), "-PT15M");
+        assert_eq!(format(Duration::weeks(1)), "P7D");
+        assert_eq!(format(Duration::days(1) + Duration::hours(12)), "P1DT12H");
+        assert_eq!(
+            format(Duration::hours(1) + Duration::seconds(30)),
+            "PT1H30S"
+        );
+        assert_eq!(format(Duration::zero()), "PT0S");
+    }
+
+    #[test]
+    fn utc_offsets() {
+        assert_eq!(Value::UtcOffset(3600).to_string(), "+0100");
+        assert_eq!(Value::UtcOffset(-5 * 3600 - 30 * 60).to_string(), "-0530");
+        assert_eq!(Value::UtcOffset(-(17 * 60 + 30)).to_string(), "-001730");
+        assert_eq!(Value::UtcOffset(0).to_string(), "+0000");
+    }
+}
@@ -1,15 +1,32 @@
+use crate::clock::{Clock, ContentStamp, SystemClock};
+use crate::holidays::HolidayCalendar;
+use crate::ical::is_color_name;
 use crate::ical::Calendar;
+use crate::model::{Session, TimetableMetadata};
 use crate::parse::Parser;
-use chrono::{DateTime, Utc};
+use crate::util::fnv1a;
+use chrono::{DateTime, NaiveDate, TimeZone as _, Utc};
 use chrono_tz::Tz;
+use once_cell::sync::OnceCell;
 use reqwest::{Response, Url};
 use scraper::Html;
 use std::convert::Into;
 use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::result::Result as StdResult;
+use std::str::FromStr;
+use std::sync::Arc;
 
+pub mod clock;
+pub mod holidays;
 pub mod ical;
+pub mod model;
 mod parse;
+mod render;
+
+/// The span of time taken by the [`TimetableOptions`] that need one.
+pub use chrono::Duration;
+pub use parse::{Diagnostic, ErrorContext, ParseError, ParseErrorKind, ReportedItem, Severity};
 pub(crate) mod util;
 
 // todo: replace by proper error type.
@@ -21,6 +38,53 @@ pub type Result<T> = StdResult<T, Box<dyn Error>>;
 pub const UC3M_TIMEZONE: Tz = chrono_tz::Europe::Madrid;
 static UC3M_TIMETABLE_DOMAIN: &str = "aplicaciones.uc3m.es";
 
+/// The language a timetable page is published in.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub enum Language {
+    Spanish,
+    English,
+}
+
+impl Language {
+    /// Returns the ISO 639-1 code of the language.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Language::Spanish => "es",
+            Language::English => "en",
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Language {
+    type Err = UnknownLanguage;
+
+    fn from_str(code: &str) -> StdResult<Self, Self::Err> {
+        match code {
+            "es" => Ok(Language::Spanish),
+            "en" => Ok(Language::English),
+            _ => Err(UnknownLanguage),
+        }
+    }
+}
+
+/// The error returned when parsing an unsupported [`Language`] code.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct UnknownLanguage;
+
+impl Display for UnknownLanguage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("unknown language code, expected `es` or `en`")
+    }
+}
+
+impl Error for UnknownLanguage {}
+
 /// Identifies a timetable.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub struct TimetableId {
@@ -31,6 +95,7 @@ pub struct TimetableId {
     group: u16,
     period: u8,
     time_zone: Tz,
+    language: Option<Language>,
 }
 
 impl TimetableId {
@@ -52,16 +117,25 @@ impl TimetableId {
             group,
             period,
             time_zone,
+            language: None,
         }
     }
 
+    /// Sets the language of the timetable page. By default, the Spanish
+    /// page is requested and the language of parsed pages is detected
+    /// from their weekday headers.
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+        self
+    }
+
     /// Returns the [`Url`] where the timetable is located.
     pub fn url(&self) -> Url {
         let url = format!(
             "https://{}/horarios-web/publicacion/{}/porCentroPlanCursoGrupo.tt",
             UC3M_TIMETABLE_DOMAIN, self.year
         );
-        let params = [
+        let mut params = vec![
             ("plan", self.plan.to_string()),
             ("centro", self.center.to_string()),
             ("curso", self.grade.to_string()),
@@ -69,33 +143,219 @@ impl TimetableId {
             ("tipoPer", "C".into()),
             ("valorPer", self.period.to_string()),
         ];
+        if let Some(language) = self.language {
+            params.push(("lang", language.code().into()));
+        }
         Url::parse_with_params(&url, &params).expect("invalid timetable url")
     }
+
+    /// Returns the calendar year in which the given `month` (starting
+    /// from 1) falls within the timetable's academic year.
+    ///
+    /// Academic years start in September, so the months of the autumn
+    /// belong to the year the timetable is identified by, and the rest
+    /// to the following year. August is assigned according to the period:
+    /// it precedes the first semester, but follows the second one
+    /// (e.g. extraordinary exams).
+    pub const fn calendar_year(&self, month: u32) -> i32 {
+        let first_month = if self.period <= 1 { 8 } else { 9 };
+        if month >= first_month {
+            self.year
+        } else {
+            self.year + 1
+        }
+    }
+}
+
+/// Settings that alter how a [`Timetable`] is generated.
+#[derive(Debug, Default, Clone)]
+pub struct TimetableOptions {
+    kind_in_summary: bool,
+    lenient: bool,
+    global_time_zones: bool,
+    holidays: Option<HolidayCalendar>,
+    reminders: Vec<Duration>,
+    source: Option<String>,
+    refresh_interval: Option<Duration>,
+    color: Option<String>,
+    clock: Option<Arc<dyn Clock>>,
+    previous_stamp: Option<ContentStamp>,
+    content_stamp: bool,
+}
+
+impl TimetableOptions {
+    /// Creates the default timetable options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines whether the summary of each event is prefixed
+    /// by its [session kind](model::SessionKind).
+    pub fn kind_in_summary(mut self, enabled: bool) -> Self {
+        self.kind_in_summary = enabled;
+        self
+    }
+
+    /// Defines whether malformed rows, cells and sessions are skipped
+    /// instead of failing the whole timetable, and whether malformed page
+    /// metadata is replaced by the metadata known from the [`TimetableId`].
+    /// The skipped items are reported by [`Timetable::diagnostics`].
+    pub fn lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
+    }
+
+    /// Defines whether the calendar refers to its time zone by a global
+    /// ID instead of including a `VTIMEZONE` definition.
+    pub fn global_time_zones(mut self, enabled: bool) -> Self {
+        self.global_time_zones = enabled;
+        self
+    }
+
+    /// Defines the days on which no lectures take place, which are
+    /// excluded from the sessions of the timetable. The calendar keeps
+    /// the excluded occurrences as cancelled events.
+    pub fn holidays(mut self, holidays: HolidayCalendar) -> Self {
+        self.holidays = Some(holidays);
+        self
+    }
+
+    /// Adds a reminder that notifies the user the given amount of time
+    /// before every session.
+    pub fn reminder(mut self, before: Duration) -> Self {
+        assert!(
+            before >= Duration::zero(),
+            "reminder must not be after the session; got {}",
+            before
+        );
+        self.reminders.push(before);
+        self
+    }
+
+    /// Defines the URI from which calendar applications can refresh
+    /// the timetable calendar.
+    pub fn source<S: Into<String>>(mut self, uri: S) -> Self {
+        self.source = Some(uri.into());
+        self
+    }
+
+    /// Defines the suggested interval at which calendar applications
+    /// refresh the timetable calendar.
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = Some(interval);
+        self
+    }
+
+    /// Defines the color used to display the timetable calendar,
+    /// given as a CSS3 color name such as `turquoise`.
+    pub fn color<C: Into<String>>(mut self, color: C) -> Self {
+        let color = color.into();
+        assert!(is_color_name(&color), "invalid CSS3 color name '{}'", color);
+        self.color = Some(color);
+        self
+    }
+
+    /// Defines the clock that provides the generation timestamp of the
+    /// timetable. Defaults to the [system clock](SystemClock).
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Defines the [stamp](Timetable::stamp) of a previously generated
+    /// timetable. If the contents are unchanged, its generation timestamp
+    /// is reused instead of reading the clock, so that parsing unchanged
+    /// contents produces identical calendars.
+    pub fn previous_stamp(mut self, stamp: ContentStamp) -> Self {
+        self.previous_stamp = Some(stamp);
+        self
+    }
+
+    /// Defines whether the generation timestamp is derived from the contents
+    /// of the timetable alone, instead of the clock and the previous stamp.
+    /// Parsing unchanged contents then produces identical calendars without
+    /// keeping any state between runs. The timestamp falls within the year
+    /// before the one in which the academic year starts.
+    pub fn content_stamp(mut self, enabled: bool) -> Self {
+        self.content_stamp = enabled;
+        self
+    }
 }
 
 /// A UC3M timetable.
+#[derive(Debug)]
 pub struct Timetable {
     id: TimetableId,
-    calendar: Calendar,
+    options: TimetableOptions,
+    metadata: TimetableMetadata,
+    sessions: Vec<Session>,
+    diagnostics: Vec<Diagnostic>,
+    content_hash: u64,
     created_on: DateTime<Tz>,
+    /// The calendar rendered from the sessions, on first access.
+    calendar: OnceCell<Calendar>,
 }
 
 impl Timetable {
     /// Fetches and parses the timetable with the given ID.
     pub async fn fetch(id: TimetableId) -> Result<Self> {
+        Self::fetch_with_options(id, &TimetableOptions::default()).await
+    }
+
+    /// Fetches and parses the timetable with the given ID and options.
+    pub async fn fetch_with_options(id: TimetableId, options: &TimetableOptions) -> Result<Self> {
         let response = reqwest::get(id.url()).await?;
         let html = parse_response(response).await?;
-        Self::parse(id, &html)
+        Self::parse_with_options(id, &html, options)
     }
 
     /// Parses the timetable with the given ID.
     pub fn parse(id: TimetableId, html: &Html) -> Result<Self> {
-        let created_on = Utc::now().with_timezone(&id.time_zone);
-        let calendar = Parser::new(&id, html, &created_on).parse()?;
+        Self::parse_with_options(id, html, &TimetableOptions::default())
+    }
+
+    /// Parses the timetable with the given ID and options.
+    pub fn parse_with_options(
+        id: TimetableId,
+        html: &Html,
+        options: &TimetableOptions,
+    ) -> Result<Self> {
+        let parser = Parser::new(&id, options, html);
+        let metadata = parser.parse_metadata()?;
+        let mut sessions = parser.parse_sessions()?;
+        if let Some(holidays) = &options.holidays {
+            sessions = sessions
+                .into_iter()
+                .map(|session| holidays.apply(session))
+                .filter(Session::has_occurrences)
+                .collect();
+            if sessions.is_empty() {
+                return Err(ParseError::from(ParseErrorKind::MissingSessions).into());
+            }
+        }
+        let content_hash = content_hash(&metadata, &sessions);
+        let created_on = if options.content_stamp {
+            content_instant(&metadata, content_hash)
+        } else {
+            let now = match &options.clock {
+                Some(clock) => clock.now(),
+                None => SystemClock.now(),
+            };
+            match options.previous_stamp {
+                // Never reuse a timestamp from the future.
+                Some(stamp) if stamp.hash() == content_hash => now.min(*stamp.created_on()),
+                _ => now,
+            }
+        };
         Ok(Self {
             id,
-            calendar,
-            created_on,
+            options: options.clone(),
+            metadata,
+            sessions,
+            diagnostics: parser.take_diagnostics(),
+            content_hash,
+            created_on: created_on.with_timezone(&id.time_zone),
+            calendar: OnceCell::new(),
         })
     }
 
@@ -104,14 +364,88 @@ impl Timetable {
         &self.id
     }
 
+    /// Returns the degree, course and group the timetable belongs to.
+    pub const fn metadata(&self) -> &TimetableMetadata {
+        &self.metadata
+    }
+
+    /// Returns the lecture sessions of the timetable.
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// Returns the malformed items skipped while parsing the timetable
+    /// in [lenient](TimetableOptions::lenient) mode, along with the
+    /// suspicious items that were kept in either mode.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     /// Returns the timetable contents as an iCalendar object.
-    pub const fn calendar(&self) -> &Calendar {
-        &self.calendar
+    pub fn calendar(&self) -> &Calendar {
+        self.calendar.get_or_init(|| render::calendar(self))
+    }
+
+    /// Converts the timetable into its [calendar](Timetable::calendar).
+    pub fn into_calendar(mut self) -> Calendar {
+        self.calendar
+            .take()
+            .unwrap_or_else(|| render::calendar(&self))
     }
 
+    /// Returns the instant at which the timetable was generated, as given by
+    /// the [clock](TimetableOptions::clock), the
+    /// [previous stamp](TimetableOptions::previous_stamp) or the
+    /// [contents](TimetableOptions::content_stamp) of the timetable.
     pub const fn created_on(&self) -> &DateTime<Tz> {
         &self.created_on
     }
+
+    /// Returns the generation timestamp of the timetable, along with
+    /// a hash of its contents.
+    pub fn stamp(&self) -> ContentStamp {
+        ContentStamp::new(self.content_hash, self.created_on.with_timezone(&Utc))
+    }
+}
+
+/// Returns the instant within the year before the academic year of the
+/// timetable that corresponds to the given hash of its contents.
+fn content_instant(metadata: &TimetableMetadata, content_hash: u64) -> DateTime<Utc> {
+    let start = Utc
+        .with_ymd_and_hms(metadata.academic_year() - 1, 1, 1, 0, 0, 0)
+        .unwrap();
+    let seconds = Duration::days(365).num_seconds() as u64;
+    start + Duration::seconds((content_hash % seconds) as i64)
+}
+
+/// Computes a hash of the timetable contents, which changes whenever
+/// the contents change.
+fn content_hash(metadata: &TimetableMetadata, sessions: &[Session]) -> u64 {
+    let mut contents = metadata.to_string();
+    for session in sessions {
+        contents.push_str(&format!(
+            "\n{}/{}/{}/{}/{}/{}/{}/{}",
+            session.subject(),
+            session.kind().map_or("", |kind| kind.name()),
+            session.weekday(),
+            session.start_time(),
+            session.duration().num_minutes(),
+            session
+                .dates()
+                .iter()
+                .map(|range| format!("{}:{}", range.start(), range.end()))
+                .collect::<Vec<_>>()
+                .join(","),
+            session
+                .excluded_dates()
+                .iter()
+                .map(NaiveDate::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+            session.room()
+        ));
+    }
+    fnv1a(contents.as_bytes())
 }
 
 #[allow(unused_mut)]
@@ -140,11 +474,36 @@ async fn parse_response(mut response: Response) -> Result<Html> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{TimetableId, Url, UC3M_TIMEZONE};
+    use crate::{Language, TimetableId, UC3M_TIMEZONE};
 
     #[test]
     fn timetable_to_url() {
         let timetable = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
         assert_eq!(timetable.url().to_string(), "https://aplicaciones.uc3m.es/horarios-web/publicacion/2022/porCentroPlanCursoGrupo.tt?plan=433&centro=2&curso=4&grupo=121&tipoPer=C&valorPer=1");
+
+        let timetable = timetable.language(Language::English);
+        assert_eq!(timetable.url().to_string(), "https://aplicaciones.uc3m.es/horarios-web/publicacion/2022/porCentroPlanCursoGrupo.tt?plan=433&centro=2&curso=4&grupo=121&tipoPer=C&valorPer=1&lang=en");
+    }
+
+    #[test]
+    fn language_codes() {
+        assert_eq!("en".parse(), Ok(Language::English));
+        assert_eq!(Language::Spanish.to_string(), "es");
+        assert!("fr".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn academic_calendar_year() {
+        let first = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+        assert_eq!(first.calendar_year(8), 2022);
+        assert_eq!(first.calendar_year(9), 2022);
+        assert_eq!(first.calendar_year(12), 2022);
+        assert_eq!(first.calendar_year(1), 2023);
+
+        let second = TimetableId::new(2022, 433, 2, 4, 121, 2, UC3M_TIMEZONE);
+        assert_eq!(second.calendar_year(12), 2022);
+        assert_eq!(second.calendar_year(2), 2023);
+        assert_eq!(second.calendar_year(5), 2023);
+        assert_eq!(second.calendar_year(8), 2023);
     }
 }
@@ -0,0 +1,455 @@
+use chrono::{Duration, NaiveDate, NaiveTime, Weekday};
+use std::fmt::{Display, Formatter};
+
+/// The kind of a lecture session, as labelled in the timetable.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub enum SessionKind {
+    /// A lecture taught to the whole group (`MAGISTRAL`).
+    Lecture,
+    /// A session taught to a subgroup of the students.
+    ReducedGroup,
+    /// A practical session taught in a laboratory.
+    Laboratory,
+    /// A seminar.
+    Seminar,
+    /// An exam.
+    Exam,
+    /// A session whose label is not known; contains the raw label.
+    Other(String),
+}
+
+impl SessionKind {
+    /// Interprets the label shown at the top of a timetable cell,
+    /// in either Spanish or English.
+    pub fn from_label(label: &str) -> Self {
+        let label = label.trim();
+        match label.to_uppercase().as_str() {
+            "MAGISTRAL" | "LECTURE" => SessionKind::Lecture,
+            "REDUCIDO" | "GRUPO REDUCIDO" | "REDUCED GROUP" => SessionKind::ReducedGroup,
+            "LABORATORIO" | "PRÁCTICAS" | "PRACTICAS" | "LABORATORY" => SessionKind::Laboratory,
+            "SEMINARIO" | "SEMINAR" => SessionKind::Seminar,
+            "EXAMEN" | "EXAM" => SessionKind::Exam,
+            _ => SessionKind::Other(label.to_string()),
+        }
+    }
+
+    /// Returns a human-readable name of the session kind.
+    pub fn name(&self) -> &str {
+        match self {
+            SessionKind::Lecture => "Lecture",
+            SessionKind::ReducedGroup => "Reduced group",
+            SessionKind::Laboratory => "Laboratory",
+            SessionKind::Seminar => "Seminar",
+            SessionKind::Exam => "Exam",
+            SessionKind::Other(label) => label,
+        }
+    }
+}
+
+impl Display for SessionKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A subject taught to a group of students.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Subject {
+    code: u32,
+    name: String,
+    group: u16,
+}
+
+impl Subject {
+    /// Creates a subject, where `code` is the numeric course code
+    /// and `group` is the number of the group of students.
+    pub fn new<N: Into<String>>(code: u32, name: N, group: u16) -> Self {
+        Self {
+            code,
+            name: name.into(),
+            group,
+        }
+    }
+
+    /// Returns the numeric course code of the subject.
+    pub const fn code(&self) -> u32 {
+        self.code
+    }
+
+    /// Returns the title of the subject.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of the group of students.
+    pub const fn group(&self) -> u16 {
+        self.group
+    }
+}
+
+impl Display for Subject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}, grp.{}", self.code, self.name, self.group)
+    }
+}
+
+/// Describes the degree, course and group a timetable belongs to.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TimetableMetadata {
+    academic_year: i32,
+    semester: u8,
+    school: String,
+    campus: String,
+    degree: String,
+    course_year: u8,
+    group: u16,
+}
+
+impl TimetableMetadata {
+    /// Creates the metadata of a timetable, where `academic_year` is
+    /// the calendar year in which the academic year starts.
+    pub fn new<S, C, D>(
+        academic_year: i32,
+        semester: u8,
+        school: S,
+        campus: C,
+        degree: D,
+        course_year: u8,
+        group: u16,
+    ) -> Self
+    where
+        S: Into<String>,
+        C: Into<String>,
+        D: Into<String>,
+    {
+        Self {
+            academic_year,
+            semester,
+            school: school.into(),
+            campus: campus.into(),
+            degree: degree.into(),
+            course_year,
+            group,
+        }
+    }
+
+    /// Returns the calendar year in which the academic year starts.
+    pub const fn academic_year(&self) -> i32 {
+        self.academic_year
+    }
+
+    /// Returns the semester number within the academic year.
+    pub const fn semester(&self) -> u8 {
+        self.semester
+    }
+
+    /// Returns the name of the school or faculty that teaches the degree.
+    pub fn school(&self) -> &str {
+        &self.school
+    }
+
+    /// Returns the name of the campus where the lectures take place,
+    /// or an empty string if it is unknown.
+    pub fn campus(&self) -> &str {
+        &self.campus
+    }
+
+    /// Returns the name of the degree.
+    pub fn degree(&self) -> &str {
+        &self.degree
+    }
+
+    /// Returns the year of the degree the course belongs to.
+    pub const fn course_year(&self) -> u8 {
+        self.course_year
+    }
+
+    /// Returns the number of the group of students.
+    pub const fn group(&self) -> u16 {
+        self.group
+    }
+
+    /// Returns a short name that identifies the timetable.
+    pub fn name(&self) -> String {
+        format!(
+            "{}, year {}, group {}",
+            self.degree, self.course_year, self.group
+        )
+    }
+}
+
+impl Display for TimetableMetadata {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}/{}, semester {}, {}",
+            self.name(),
+            self.academic_year,
+            self.academic_year + 1,
+            self.semester,
+            self.school
+        )?;
+        // The campus is unknown if the page header is missing.
+        if !self.campus.is_empty() {
+            write!(f, ", {}", self.campus)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// A lecture session of a subject that takes place weekly on
+/// the given dates.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Session {
+    subject: Subject,
+    kind: Option<SessionKind>,
+    weekday: Weekday,
+    start_time: NaiveTime,
+    duration: Duration,
+    dates: Vec<DateRange>,
+    excluded_dates: Vec<NaiveDate>,
+    room: String,
+}
+
+impl Session {
+    /// Creates a session that takes place every `weekday` within
+    /// the given non-empty date ranges, from `start_time` during
+    /// a positive `duration`.
+    pub fn new<R: Into<String>>(
+        subject: Subject,
+        kind: Option<SessionKind>,
+        weekday: Weekday,
+        start_time: NaiveTime,
+        duration: Duration,
+        dates: Vec<DateRange>,
+        room: R,
+    ) -> Self {
+        assert!(!dates.is_empty(), "session must have >= 1 date ranges");
+        assert!(
+            duration > Duration::zero(),
+            "session duration must be positive; got {}",
+            duration
+        );
+        Self {
+            subject,
+            kind,
+            weekday,
+            start_time,
+            duration,
+            dates,
+            excluded_dates: Vec::new(),
+            room: room.into(),
+        }
+    }
+
+    /// Excludes the occurrences of the session that fall on the given dates.
+    /// The dates that aren't occurrences of the session are ignored.
+    pub fn excluding<I: IntoIterator<Item = NaiveDate>>(mut self, dates: I) -> Self {
+        let occurrences: Vec<_> = self.occurrences().collect();
+        self.excluded_dates
+            .extend(dates.into_iter().filter(|date| occurrences.contains(date)));
+        self.excluded_dates.sort_unstable();
+        self.excluded_dates.dedup();
+        self
+    }
+
+    /// Returns the subject taught in the session.
+    pub const fn subject(&self) -> &Subject {
+        &self.subject
+    }
+
+    /// Returns the kind of the session, if known.
+    pub const fn kind(&self) -> Option<&SessionKind> {
+        self.kind.as_ref()
+    }
+
+    /// Returns the day of the week the session takes place on.
+    pub const fn weekday(&self) -> Weekday {
+        self.weekday
+    }
+
+    /// Returns the local time at which the session starts.
+    pub const fn start_time(&self) -> NaiveTime {
+        self.start_time
+    }
+
+    /// Returns the duration of the session.
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the date ranges within which the session takes place weekly.
+    pub fn dates(&self) -> &[DateRange] {
+        &self.dates
+    }
+
+    /// Returns the dates within the date ranges on which the session
+    /// does not take place, in chronological order.
+    pub fn excluded_dates(&self) -> &[NaiveDate] {
+        &self.excluded_dates
+    }
+
+    /// Returns the dates on which the session takes place weekly within
+    /// each date range, including the excluded dates.
+    pub fn occurrences(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.dates.iter().flat_map(|range| {
+            (0..)
+                .map(move |week| range.start() + Duration::weeks(week))
+                .take_while(move |&date| date <= range.end())
+        })
+    }
+
+    /// Tests if the session takes place on any date that isn't excluded.
+    pub fn has_occurrences(&self) -> bool {
+        self.occurrences()
+            .any(|date| !self.excluded_dates.contains(&date))
+    }
+
+    /// Returns the room where the session takes place.
+    pub fn room(&self) -> &str {
+        &self.room
+    }
+}
+
+/// An inclusive range of dates.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub struct DateRange {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl DateRange {
+    /// Creates a date range, where `start` is not after `end`.
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Self {
+        assert!(
+            start <= end,
+            "date range start must not be after its end; got {} > {}",
+            start,
+            end
+        );
+        Self { start, end }
+    }
+
+    /// Creates a range containing a single date.
+    pub const fn single(date: NaiveDate) -> Self {
+        Self {
+            start: date,
+            end: date,
+        }
+    }
+
+    /// Returns the first date of the range.
+    pub const fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// Returns the last date of the range.
+    pub const fn end(&self) -> NaiveDate {
+        self.end
+    }
+
+    /// Tests if the range contains a single date.
+    pub fn is_single_day(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Tests if the range contains the given date.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{DateRange, Session, SessionKind, Subject, TimetableMetadata};
+    use chrono::{Duration, NaiveDate, NaiveTime, Weekday};
+
+    #[test]
+    fn session_kind_from_label() {
+        assert_eq!(SessionKind::from_label("MAGISTRAL"), SessionKind::Lecture);
+        assert_eq!(SessionKind::from_label("Lecture"), SessionKind::Lecture);
+        assert_eq!(
+            SessionKind::from_label(" Laboratorio "),
+            SessionKind::Laboratory
+        );
+        assert_eq!(
+            SessionKind::from_label("TALLER"),
+            SessionKind::Other("TALLER".into())
+        );
+        assert_eq!(SessionKind::Lecture.to_string(), "Lecture");
+        assert_eq!(SessionKind::Other("TALLER".into()).to_string(), "TALLER");
+    }
+
+    #[test]
+    fn display_subject() {
+        let subject = Subject::new(18281, "ANÁLISIS FUNCIONAL APLICADO", 121);
+        assert_eq!(
+            subject.to_string(),
+            "18281-ANÁLISIS FUNCIONAL APLICADO, grp.121"
+        );
+    }
+
+    #[test]
+    fn display_metadata() {
+        let metadata = TimetableMetadata::new(
+            2022,
+            1,
+            "Escuela Politécnica Superior",
+            "Leganés",
+            "Grado en Matemática Aplicada y Computación",
+            4,
+            121,
+        );
+        assert_eq!(
+            metadata.name(),
+            "Grado en Matemática Aplicada y Computación, year 4, group 121"
+        );
+        assert_eq!(metadata.to_string(), "Grado en Matemática Aplicada y Computación, year 4, group 121 (2022/2023, semester 1, Escuela Politécnica Superior, Leganés)");
+    }
+
+    #[test]
+    fn session_exclusions() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2022, month, day).unwrap();
+        let session = Session::new(
+            Subject::new(18284, "CRIPTOGRAFÍA", 121),
+            None,
+            Weekday::Thu,
+            NaiveTime::from_hms_opt(11, 0, 0).unwrap(),
+            Duration::hours(2),
+            vec![
+                DateRange::new(date(9, 29), date(10, 13)),
+                DateRange::single(date(12, 1)),
+            ],
+            "Aula 2.2.D08",
+        );
+        assert_eq!(
+            session.occurrences().collect::<Vec<_>>(),
+            vec![date(9, 29), date(10, 6), date(10, 13), date(12, 1)]
+        );
+
+        let session = session.excluding([date(10, 12), date(10, 6), date(12, 1)]);
+        assert_eq!(session.excluded_dates(), &[date(10, 6), date(12, 1)]);
+        assert!(session.has_occurrences());
+        let session = session.excluding([date(9, 29), date(10, 13)]);
+        assert!(!session.has_occurrences());
+    }
+
+    #[test]
+    fn date_range() {
+        let start = NaiveDate::from_ymd_opt(2022, 12, 12).unwrap();
+        let end = NaiveDate::from_ymd_opt(2023, 1, 16).unwrap();
+        let range = DateRange::new(start, end);
+        assert!(!range.is_single_day());
+        assert!(range.contains(NaiveDate::from_ymd_opt(2023, 1, 2).unwrap()));
+        assert!(!range.contains(NaiveDate::from_ymd_opt(2023, 1, 17).unwrap()));
+        assert!(DateRange::single(start).is_single_day());
+    }
+
+    #[test]
+    #[should_panic]
+    fn reversed_date_range() {
+        let start = NaiveDate::from_ymd_opt(2023, 1, 16).unwrap();
+        let end = NaiveDate::from_ymd_opt(2022, 12, 12).unwrap();
+        DateRange::new(start, end);
+    }
+}
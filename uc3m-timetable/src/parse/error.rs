@@ -0,0 +1,433 @@
+use chrono::{Duration, NaiveDate, NaiveTime, Weekday};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+
+/// The maximum number of characters of a raw snippet kept by an [`ErrorContext`].
+const MAX_SNIPPET_LEN: usize = 80;
+
+/// An error that occurred while parsing a timetable, along with
+/// the location in the page where it happened.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    // Boxed to keep the `Err` variant of parsing results small.
+    context: Box<ErrorContext>,
+}
+
+impl ParseError {
+    /// Returns the cause of the error.
+    pub const fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Returns the location in the page where the error happened.
+    pub const fn context(&self) -> &ErrorContext {
+        &self.context
+    }
+
+    /// Sets the start time of the row where the error happened,
+    /// unless it is already known.
+    pub(crate) fn at_time(mut self, time: NaiveTime) -> Self {
+        self.context.time.get_or_insert(time);
+        self
+    }
+
+    /// Sets the column of the cell where the error happened, and
+    /// the text of the column header, unless they are already known.
+    pub(crate) fn at_column(mut self, column: usize, header: Option<&str>) -> Self {
+        if self.context.column.is_none() {
+            self.context.column = Some(column);
+            self.context.header = header.map(str::to_string);
+        }
+        self
+    }
+
+    /// Sets the text of the subject whose cell contains the error,
+    /// unless it is already known.
+    pub(crate) fn in_subject(mut self, subject: &str) -> Self {
+        self.context
+            .subject
+            .get_or_insert_with(|| subject.trim().to_string());
+        self
+    }
+
+    /// Sets the raw text that could not be parsed, unless it is already known.
+    pub(crate) fn near(mut self, snippet: &str) -> Self {
+        self.context.snippet.get_or_insert_with(|| {
+            let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+            match snippet.char_indices().nth(MAX_SNIPPET_LEN) {
+                Some((end, _)) => format!("{}...", &snippet[..end]),
+                None => snippet,
+            }
+        });
+        self
+    }
+}
+
+impl From<ParseErrorKind> for ParseError {
+    fn from(kind: ParseErrorKind) -> Self {
+        Self {
+            kind,
+            context: Box::default(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.context.is_empty() {
+            write!(f, " ({})", self.context)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.kind.source()
+    }
+}
+
+/// The location in the page where a [`ParseError`] happened.
+///
+/// Each field is only known if the error happened within
+/// the corresponding element.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct ErrorContext {
+    time: Option<NaiveTime>,
+    column: Option<usize>,
+    header: Option<String>,
+    subject: Option<String>,
+    snippet: Option<String>,
+}
+
+impl ErrorContext {
+    /// Returns the start time of the row, as shown by its `cabeceraHora` cell.
+    pub const fn time(&self) -> Option<NaiveTime> {
+        self.time
+    }
+
+    /// Returns the index of the weekday column, starting from 0.
+    pub const fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    /// Returns the text of the weekday column header.
+    pub fn header(&self) -> Option<&str> {
+        self.header.as_deref()
+    }
+
+    /// Returns the raw text of the subject of the cell.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    /// Returns the raw text that could not be parsed.
+    pub fn snippet(&self) -> Option<&str> {
+        self.snippet.as_deref()
+    }
+
+    /// Tests if no location information is known.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::with_capacity(4);
+        if let Some(time) = &self.time {
+            parts.push(format!("row {}", time.format("%H:%M")));
+        }
+        if let Some(column) = &self.column {
+            parts.push(match &self.header {
+                Some(header) => format!("column {} ({})", column, header),
+                None => format!("column {}", column),
+            });
+        }
+        if let Some(subject) = &self.subject {
+            parts.push(format!("subject `{}`", subject));
+        }
+        if let Some(snippet) = &self.snippet {
+            parts.push(format!("near `{}`", snippet));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// The cause of a [`ParseError`].
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    MissingHeaderElem,
+    InvalidHeaderFormat,
+    MissingSubtitleElem,
+    InvalidSubtitleFormat,
+    MismatchedMetadata,
+    MissingTbodyElem,
+    MissingSessions,
+    MissingRowTimeCell,
+    ChildlessTimeElement,
+    NonTextualTimeNode,
+    NonElementMinutesNode,
+    NonNumericTimeValue(String, ParseIntError),
+    InvalidTimeValue(u32, u32),
+    UnevenTimeGrid(Duration, Duration),
+    InvalidRowSpan(String, ParseIntError),
+    MismatchedTimeLabel(String, NaiveTime, NaiveTime),
+    MissingGroupElem,
+    ChildlessGroupElem,
+    NonTextualGroupChild,
+    MissingSessionsElem,
+    NonElementSessionDateNode,
+    NonElementSessionLocationNode,
+    MissingDateRange,
+    NonTextualDateRange,
+    MissingLocationSpan,
+    NonTextualLocationSpan,
+    InvalidDateFormat(String),
+    InvalidDay(String, ParseIntError),
+    InvalidMonth(String),
+    InvalidDate(u32, u32),
+    ReversedDateRange,
+    UnknownWeekday(Option<String>),
+    MismatchedWeekday(NaiveDate, Weekday),
+    InvalidSubjectFormat,
+    InvalidSubjectCode(String, ParseIntError),
+    InvalidSubjectGroup(String, ParseIntError),
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::MissingHeaderElem => {
+                f.write_str("cannot find the `#nombreApp` header element")
+            }
+            ParseErrorKind::InvalidHeaderFormat => f.write_str(
+                "header does not follow the `year/year, semester, school (campus)` format",
+            ),
+            ParseErrorKind::MissingSubtitleElem => {
+                f.write_str("cannot find the `h2.subtitulo` element")
+            }
+            ParseErrorKind::InvalidSubtitleFormat => {
+                f.write_str("subtitle does not follow the `degree, year curso, grupo group` format")
+            }
+            ParseErrorKind::MismatchedMetadata => {
+                f.write_str("timetable metadata does not match the requested timetable")
+            }
+            ParseErrorKind::MissingTbodyElem => {
+                f.write_str("cannot find the time table `tbody` element")
+            }
+            ParseErrorKind::MissingSessions => f.write_str("time table contains no sessions"),
+            ParseErrorKind::MissingRowTimeCell => {
+                f.write_str("cannot find the `hh:mm` cell of the time table row")
+            }
+            ParseErrorKind::ChildlessTimeElement => f.write_str("time element has no children"),
+            ParseErrorKind::NonTextualTimeNode => {
+                f.write_str("first child of the time element is not a textual node")
+            }
+            ParseErrorKind::NonElementMinutesNode => {
+                f.write_str("last child of the time cell is not an element")
+            }
+            ParseErrorKind::NonNumericTimeValue(value, _) => {
+                write!(f, "time cell has a non-numeric time value `{}`", value)
+            }
+            ParseErrorKind::InvalidTimeValue(hour, minutes) => write!(
+                f,
+                "time cell has an out of range time value `{}:{}`",
+                hour, minutes
+            ),
+            ParseErrorKind::UnevenTimeGrid(slot, step) => write!(
+                f,
+                "time grid rows are {} minutes apart, but this row starts {} minutes after the previous one",
+                slot.num_minutes(),
+                step.num_minutes()
+            ),
+            ParseErrorKind::InvalidRowSpan(value, _) => write!(
+                f,
+                "element has an invalid `rowspan` attribute value `{}`",
+                value
+            ),
+            ParseErrorKind::MismatchedTimeLabel(label, start, end) => write!(
+                f,
+                "time label `{}` does not match the cell position in the grid, from {} to {}",
+                label,
+                start.format("%H:%M"),
+                end.format("%H:%M")
+            ),
+            ParseErrorKind::MissingGroupElem => {
+                f.write_str("cannot find the subject group element of cell element")
+            }
+            ParseErrorKind::ChildlessGroupElem => f.write_str("cell group element has no children"),
+            ParseErrorKind::NonTextualGroupChild => {
+                f.write_str("first child of the subject group element is not a textual node")
+            }
+            ParseErrorKind::MissingSessionsElem => {
+                f.write_str("cannot find the sessions element of subject group element")
+            }
+            ParseErrorKind::NonElementSessionDateNode => {
+                f.write_str("session date node is not an element")
+            }
+            ParseErrorKind::NonElementSessionLocationNode => {
+                f.write_str("session location node is not an element")
+            }
+            ParseErrorKind::MissingDateRange => {
+                f.write_str("session within a cell is missing date range")
+            }
+            ParseErrorKind::NonTextualDateRange => {
+                f.write_str("first child of date range element is not a textual node")
+            }
+            ParseErrorKind::MissingLocationSpan => {
+                f.write_str("cannot find the location span of session")
+            }
+            ParseErrorKind::NonTextualLocationSpan => {
+                f.write_str("location span of a session is not a textual node")
+            }
+            ParseErrorKind::InvalidDateFormat(date) => write!(
+                f,
+                "formatted date `{}` does not follow the `dd.month` format",
+                date
+            ),
+            ParseErrorKind::InvalidDay(day, _) => write!(f, "invalid day value `{}`", day),
+            ParseErrorKind::InvalidMonth(month) => write!(f, "invalid month value `{}`", month),
+            ParseErrorKind::InvalidDate(day, month) => {
+                write!(f, "day {} does not exist in month {}", day, month)
+            }
+            ParseErrorKind::ReversedDateRange => {
+                f.write_str("end date of session precedes its start date")
+            }
+            ParseErrorKind::UnknownWeekday(Some(header)) => {
+                write!(f, "column header `{}` is not a weekday", header)
+            }
+            ParseErrorKind::UnknownWeekday(None) => f.write_str("column has no weekday header"),
+            ParseErrorKind::MismatchedWeekday(date, weekday) => write!(
+                f,
+                "date {} does not fall on the {} of its column",
+                date.format("%d-%m-%Y"),
+                weekday
+            ),
+            ParseErrorKind::InvalidSubjectFormat => {
+                f.write_str("subject does not follow the `code-name, grp.group` format")
+            }
+            ParseErrorKind::InvalidSubjectCode(code, _) => {
+                write!(f, "invalid subject code `{}`", code)
+            }
+            ParseErrorKind::InvalidSubjectGroup(group, _) => {
+                write!(f, "invalid subject group number `{}`", group)
+            }
+        }
+    }
+}
+
+impl Error for ParseErrorKind {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseErrorKind::NonNumericTimeValue(_, err)
+            | ParseErrorKind::InvalidRowSpan(_, err)
+            | ParseErrorKind::InvalidDay(_, err)
+            | ParseErrorKind::InvalidSubjectCode(_, err)
+            | ParseErrorKind::InvalidSubjectGroup(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// An item of the timetable reported by the parser.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ReportedItem {
+    /// The page header and subtitle describing the timetable.
+    Metadata,
+    /// A row of cells starting at the same time.
+    Row,
+    /// A cell containing the sessions of a subject.
+    Cell,
+    /// A date range and location within a cell.
+    Session,
+}
+
+impl Display for ReportedItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match *self {
+            ReportedItem::Metadata => "metadata",
+            ReportedItem::Row => "row",
+            ReportedItem::Cell => "cell",
+            ReportedItem::Session => "session",
+        })
+    }
+}
+
+/// The severity of a [`Diagnostic`].
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
+pub enum Severity {
+    /// The item is suspicious, but was kept.
+    Warning,
+    /// The item is malformed and was skipped.
+    Error,
+}
+
+/// A problem found while parsing a timetable. Malformed items are only
+/// reported in lenient mode, while suspicious items are always reported.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub(crate) severity: Severity,
+    pub(crate) item: ReportedItem,
+    pub(crate) error: ParseError,
+}
+
+impl Diagnostic {
+    /// Returns whether the item was kept or skipped.
+    pub const fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the kind of item the problem was found in.
+    pub const fn item(&self) -> ReportedItem {
+        self.item
+    }
+
+    /// Returns the problem found in the item.
+    pub const fn error(&self) -> &ParseError {
+        &self.error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let action = match self.severity {
+            Severity::Warning => "kept",
+            Severity::Error => "skipped",
+        };
+        write!(f, "{} {}: {}", action, self.item, self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::error::{ParseError, ParseErrorKind};
+    use chrono::NaiveTime;
+
+    #[test]
+    fn display_context() {
+        let error = ParseError::from(ParseErrorKind::InvalidMonth("sept".into()));
+        assert_eq!(error.to_string(), "invalid month value `sept`");
+
+        let error = error
+            .near("05.sept-24.oct")
+            .in_subject("18281-ANÁLISIS FUNCIONAL APLICADO, grp.121\u{a0}\u{a0}")
+            .at_column(0, Some("LUNES"))
+            .at_time(NaiveTime::from_hms_opt(15, 0, 0).unwrap())
+            .near("ignored");
+        assert_eq!(error.context().snippet(), Some("05.sept-24.oct"));
+        assert_eq!(error.to_string(), "invalid month value `sept` (row 15:00, column 0 (LUNES), subject `18281-ANÁLISIS FUNCIONAL APLICADO, grp.121`, near `05.sept-24.oct`)");
+    }
+
+    #[test]
+    fn long_snippet() {
+        let error = ParseError::from(ParseErrorKind::MissingGroupElem).near(&"a ".repeat(100));
+        assert_eq!(
+            error.context().snippet().unwrap(),
+            format!("{}...", "a ".repeat(40))
+        );
+    }
+}
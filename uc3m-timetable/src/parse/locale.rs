@@ -0,0 +1,82 @@
+use crate::Language;
+use chrono::Weekday;
+
+static SPANISH_MONTHS: [&str; 12] = [
+    "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic",
+];
+static ENGLISH_MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Returns the number (starting from 1) of the month abbreviated as `month`.
+pub fn month(language: Language, month: &str) -> Option<u32> {
+    let months = match language {
+        Language::Spanish => &SPANISH_MONTHS,
+        Language::English => &ENGLISH_MONTHS,
+    };
+    let month = month.trim().to_lowercase();
+    months
+        .iter()
+        .position(|name| *name == month)
+        .map(|index| index as u32 + 1)
+}
+
+/// Returns the weekday named by a column header of the timetable.
+pub fn weekday(language: Language, header: &str) -> Option<Weekday> {
+    let header = header.trim().to_uppercase();
+    let weekday = match (language, header.as_str()) {
+        (Language::Spanish, "LUNES") | (Language::English, "MONDAY") => Weekday::Mon,
+        (Language::Spanish, "MARTES") | (Language::English, "TUESDAY") => Weekday::Tue,
+        (Language::Spanish, "MIERCOLES" | "MIÉRCOLES") | (Language::English, "WEDNESDAY") => {
+            Weekday::Wed
+        }
+        (Language::Spanish, "JUEVES") | (Language::English, "THURSDAY") => Weekday::Thu,
+        (Language::Spanish, "VIERNES") | (Language::English, "FRIDAY") => Weekday::Fri,
+        (Language::Spanish, "SABADO" | "SÁBADO") | (Language::English, "SATURDAY") => Weekday::Sat,
+        (Language::Spanish, "DOMINGO") | (Language::English, "SUNDAY") => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+/// Detects the language of a page from its weekday column headers,
+/// defaulting to Spanish.
+pub fn detect<'a, I>(headers: I) -> Language
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let is_english = headers
+        .into_iter()
+        .any(|header| weekday(Language::English, header).is_some());
+    if is_english {
+        Language::English
+    } else {
+        Language::Spanish
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::locale::{detect, month, weekday};
+    use crate::Language;
+    use chrono::Weekday;
+
+    #[test]
+    fn month_abbreviations() {
+        assert_eq!(month(Language::Spanish, "ene"), Some(1));
+        assert_eq!(month(Language::Spanish, "ago"), Some(8));
+        assert_eq!(month(Language::Spanish, "aug"), None);
+        assert_eq!(month(Language::English, "Aug"), Some(8));
+        assert_eq!(month(Language::English, "dec"), Some(12));
+    }
+
+    #[test]
+    fn weekday_headers() {
+        assert_eq!(weekday(Language::Spanish, "MIERCOLES"), Some(Weekday::Wed));
+        assert_eq!(weekday(Language::English, " Saturday "), Some(Weekday::Sat));
+        assert_eq!(weekday(Language::English, "LUNES"), None);
+        assert_eq!(detect(["LUNES", "MARTES"]), Language::Spanish);
+        assert_eq!(detect(["MONDAY", "TUESDAY"]), Language::English);
+        assert_eq!(detect([]), Language::Spanish);
+    }
+}
@@ -0,0 +1,804 @@
+use crate::model::{DateRange, Session, SessionKind, Subject, TimetableMetadata};
+use crate::util::{process, process_all};
+use crate::{Language, TimetableId, TimetableOptions};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use scraper::{ElementRef, Html, Selector};
+use selectors::attr::CaseSensitivity;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+mod error;
+mod locale;
+
+pub use error::{Diagnostic, ErrorContext, ParseError, ParseErrorKind, ReportedItem, Severity};
+
+macro_rules! selector {
+    ($selector:expr) => {
+        Lazy::new(|| Selector::parse($selector).unwrap())
+    };
+}
+
+static HEADER_SELECTOR: Lazy<Selector> = selector!("#nombreApp");
+static SUBTITLE_SELECTOR: Lazy<Selector> = selector!("h2.subtitulo");
+static TIMETABLE_SELECTOR: Lazy<Selector> = selector!(".timetable>tbody");
+static DAY_SELECTOR: Lazy<Selector> = selector!(".timetable>thead .cabeceraDia");
+static TIME_SELECTOR: Lazy<Selector> = selector!(".cabeceraHora");
+static GROUP_SELECTOR: Lazy<Selector> = selector!(".asignaturaGrupo");
+static SESSION_SELECTOR: Lazy<Selector> = selector!(".fechasSesion");
+
+/// The time spanned by each row of most timetables.
+const DEFAULT_SLOT_MINUTES: i64 = 15;
+
+#[derive(Debug)]
+pub struct Parser<'a> {
+    time_table: &'a TimetableId,
+    options: &'a TimetableOptions,
+    input: &'a Html,
+    headers: Vec<String>,
+    language: Language,
+    diagnostics: RefCell<Vec<Diagnostic>>,
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser for interpreting the given input. Unless the
+    /// timetable specifies a language, it is detected from the input.
+    pub fn new(
+        time_table: &'a TimetableId,
+        options: &'a TimetableOptions,
+        input: &'a Html,
+    ) -> Self {
+        let headers: Vec<String> = input
+            .select(&DAY_SELECTOR)
+            .map(|elem| elem.text().collect::<String>().trim().to_string())
+            .collect();
+        let language = time_table
+            .language
+            .unwrap_or_else(|| locale::detect(headers.iter().map(String::as_str)));
+        Self {
+            time_table,
+            options,
+            input,
+            headers,
+            language,
+            diagnostics: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the diagnostics reported while parsing, leaving
+    /// an empty vector in their place.
+    pub fn take_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
+
+    /// Handles a malformed item that cannot be parsed. In lenient mode,
+    /// the item is skipped and the error is reported as a diagnostic;
+    /// otherwise, the error is returned.
+    fn skip(&self, item: ReportedItem, error: ParseError) -> Result<(), ParseError> {
+        self.report(Severity::Error, item, error)
+    }
+
+    /// Handles a suspicious item. In lenient mode, the item is kept and
+    /// the error is [noted](Parser::note); otherwise, the error is returned.
+    fn warn(&self, item: ReportedItem, error: ParseError) -> Result<(), ParseError> {
+        if self.options.lenient {
+            self.note(item, error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Reports a suspicious item that can be interpreted unambiguously,
+    /// regardless of the parsing mode.
+    fn note(&self, item: ReportedItem, error: ParseError) {
+        self.diagnostics.borrow_mut().push(Diagnostic {
+            severity: Severity::Warning,
+            item,
+            error,
+        });
+    }
+
+    fn report(
+        &self,
+        severity: Severity,
+        item: ReportedItem,
+        error: ParseError,
+    ) -> Result<(), ParseError> {
+        if self.options.lenient {
+            self.diagnostics.borrow_mut().push(Diagnostic {
+                severity,
+                item,
+                error,
+            });
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Collects the items of the `Ok` elements of `results`. In lenient mode,
+    /// the errors are reported as skipped `item`s; otherwise, the first error
+    /// is returned.
+    fn collect<T, I>(&self, results: I, item: ReportedItem) -> Result<Vec<T>, ParseError>
+    where
+        I: Iterator<Item = Result<T, ParseError>>,
+    {
+        if self.options.lenient {
+            let (items, errors) = process_all(results);
+            let items = items.collect();
+            let errors = errors.take();
+            self.diagnostics
+                .borrow_mut()
+                .extend(errors.into_iter().map(|error| Diagnostic {
+                    severity: Severity::Error,
+                    item,
+                    error,
+                }));
+            Ok(items)
+        } else {
+            let (items, result) = process(results);
+            let items = items.collect();
+            let result = result.borrow().clone();
+            result.map(|_| items)
+        }
+    }
+
+    /// Parses the timetable metadata from the page header and subtitle,
+    /// checking that it matches the requested timetable. In lenient mode,
+    /// malformed metadata is reported and replaced by the metadata known
+    /// from the timetable identifier.
+    pub fn parse_metadata(&self) -> Result<TimetableMetadata, ParseError> {
+        self.read_metadata().or_else(|err| {
+            self.skip(ReportedItem::Metadata, err)?;
+            let id = self.time_table;
+            Ok(TimetableMetadata::new(
+                id.year,
+                id.period,
+                format!("Center {}", id.center),
+                "",
+                format!("Plan {}", id.plan),
+                id.grade,
+                id.group,
+            ))
+        })
+    }
+
+    fn read_metadata(&self) -> Result<TimetableMetadata, ParseError> {
+        // The header has the form "HORARIOS → Curso 2022/2023,
+        // 1er. cuatrimestre, Escuela Politécnica Superior. (Leganés)",
+        // or "TIMETABLES → Academic year 2022/2023, 1st. semester, ..."
+        // in English. The fields start at the first digit.
+        let header = self
+            .select_text(&HEADER_SELECTOR)
+            .ok_or(ParseErrorKind::MissingHeaderElem)?;
+        let invalid_header = || ParseError::from(ParseErrorKind::InvalidHeaderFormat).near(&header);
+        let (years, semester, school) = header
+            .find(|ch: char| ch.is_ascii_digit())
+            .and_then(|start| header[start..].splitn(3, ", ").next_tuple())
+            .ok_or_else(invalid_header)?;
+        let academic_year = years
+            .split_once('/')
+            .and_then(|(start, _)| start.parse().ok())
+            .ok_or_else(invalid_header)?;
+        let semester = parse_leading_number(semester).ok_or_else(invalid_header)?;
+        let (school, campus) = school.rsplit_once('(').ok_or_else(invalid_header)?;
+
+        // The subtitle has the form "Grado en Matemática Aplicada
+        // y Computación, 4º curso, grupo 121" (or "4th year, group 121").
+        let subtitle = self
+            .select_text(&SUBTITLE_SELECTOR)
+            .ok_or(ParseErrorKind::MissingSubtitleElem)?;
+        let invalid_subtitle =
+            || ParseError::from(ParseErrorKind::InvalidSubtitleFormat).near(&subtitle);
+        let (group, course_year, degree) = subtitle
+            .rsplitn(3, ", ")
+            .next_tuple()
+            .ok_or_else(invalid_subtitle)?;
+        let course_year = parse_leading_number(course_year).ok_or_else(invalid_subtitle)?;
+        let group = group
+            .rsplit(' ')
+            .next()
+            .and_then(|group| group.parse().ok())
+            .ok_or_else(invalid_subtitle)?;
+
+        let metadata = TimetableMetadata::new(
+            academic_year,
+            semester,
+            school.trim().trim_end_matches('.'),
+            campus.trim().trim_end_matches(')'),
+            degree.trim(),
+            course_year,
+            group,
+        );
+        let id = self.time_table;
+        if metadata.academic_year() != id.year
+            || metadata.semester() != id.period
+            || metadata.course_year() != id.grade
+            || metadata.group() != id.group
+        {
+            return Err(ParseError::from(ParseErrorKind::MismatchedMetadata).near(&subtitle));
+        }
+        Ok(metadata)
+    }
+
+    /// Returns the text contained by the first element matching `selector`.
+    fn select_text(&self, selector: &Selector) -> Option<String> {
+        self.input
+            .select(selector)
+            .next()
+            .map(|elem| elem.text().collect())
+    }
+
+    /// Parses the sessions of the timetable.
+    pub fn parse_sessions(&self) -> Result<Vec<Session>, ParseError> {
+        let table_body = self
+            .input
+            .select(&TIMETABLE_SELECTOR)
+            .next()
+            .ok_or(ParseErrorKind::MissingTbodyElem)?;
+        let row_elems = table_body.children().filter_map(ElementRef::wrap);
+
+        // Assign the columns before parsing the rows, so that the cells
+        // of a skipped row still occupy their columns in the next rows.
+        let mut spans = Vec::with_capacity(self.headers.len());
+        let rows: Vec<_> = row_elems
+            .map(|row_elem| (parse_row_time(row_elem), place_cells(row_elem, &mut spans)))
+            .collect();
+        let times: Vec<_> = rows.iter().map(|(time, _)| time.as_ref().ok()).collect();
+        let slot = grid_step(&times)?;
+
+        let mut sessions = Vec::with_capacity(10); // most days have 2 sessions
+        let mut kinds = HashMap::new();
+        for (time, cell_elems) in rows {
+            let result = time.and_then(|time| {
+                self.parse_row(time, slot, &cell_elems, &mut sessions, &mut kinds)
+            });
+            if let Err(err) = result {
+                self.skip(ReportedItem::Row, err)?;
+            }
+        }
+        if sessions.is_empty() {
+            return Err(ParseErrorKind::MissingSessions.into());
+        }
+        Ok(sessions)
+    }
+
+    /// Parses the sessions of a row starting at `start_time`, given its cells
+    /// and their column indices, where each row spans a `slot` of time.
+    /// `kinds` maps the subjects seen in previous cells to their latest known
+    /// session kind.
+    fn parse_row(
+        &self,
+        start_time: NaiveTime,
+        slot: Duration,
+        cell_elems: &[(usize, ElementRef)],
+        dest: &mut Vec<Session>,
+        kinds: &mut HashMap<Subject, SessionKind>,
+    ) -> Result<(), ParseError> {
+        let cell_elems = cell_elems.iter().filter(|(_, cell_elem)| {
+            cell_elem
+                .value()
+                .has_class("celdaConSesion", CaseSensitivity::CaseSensitive)
+        });
+        let cells = self.collect(
+            cell_elems.map(|&(column, elem)| {
+                let header = self.headers.get(column).map(String::as_str);
+                Cell::new(self, start_time, slot, column, header, elem)
+            }),
+            ReportedItem::Cell,
+        )?;
+        for cell in cells {
+            if let Err(err) = cell.push_sessions(dest, kinds) {
+                self.skip(ReportedItem::Cell, err)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the start time of a row from its `cabeceraHora` cell.
+fn parse_row_time(row_elem: ElementRef) -> Result<NaiveTime, ParseError> {
+    fn get_time_text(elem: ElementRef) -> Result<u32, ParseError> {
+        let text = elem
+            .first_child()
+            .ok_or(ParseErrorKind::ChildlessTimeElement)?
+            .value()
+            .as_text()
+            .ok_or(ParseErrorKind::NonTextualTimeNode)?;
+        text.trim()
+            .parse()
+            .map_err(|err| ParseErrorKind::NonNumericTimeValue(text.to_string(), err).into())
+    }
+
+    let time_elem = row_elem
+        .select(&TIME_SELECTOR)
+        .next()
+        .ok_or(ParseErrorKind::MissingRowTimeCell)?;
+    let time_text: String = time_elem.text().collect();
+    let hour = get_time_text(time_elem).map_err(|err| err.near(&time_text))?;
+
+    // The minutes are wrapped in a <sup> element
+    let minutes_elem = ElementRef::wrap(time_elem.last_child().unwrap())
+        .ok_or_else(|| ParseError::from(ParseErrorKind::NonElementMinutesNode).near(&time_text))?;
+    let minutes = get_time_text(minutes_elem).map_err(|err| err.near(&time_text))?;
+
+    NaiveTime::from_hms_opt(hour, minutes, 0).ok_or_else(|| {
+        ParseError::from(ParseErrorKind::InvalidTimeValue(hour, minutes)).near(&time_text)
+    })
+}
+
+/// Infers the time spanned by each row of the grid from the start times of
+/// consecutive rows, ignoring those that cannot be parsed. Defaults to
+/// [`DEFAULT_SLOT_MINUTES`] if the grid has a single row.
+fn grid_step(times: &[Option<&NaiveTime>]) -> Result<Duration, ParseError> {
+    let mut slot = None;
+    for pair in times.windows(2) {
+        if let [Some(&prev), Some(&next)] = *pair {
+            let step = next - prev;
+            match slot {
+                None if step > Duration::zero() => slot = Some(step),
+                Some(slot) if step == slot => {}
+                _ => {
+                    let slot = slot.unwrap_or(step);
+                    let err = ParseErrorKind::UnevenTimeGrid(slot, step);
+                    return Err(ParseError::from(err).at_time(next));
+                }
+            }
+        }
+    }
+    Ok(slot.unwrap_or_else(|| Duration::minutes(DEFAULT_SLOT_MINUTES)))
+}
+
+/// Assigns each data cell of a row to its weekday column, skipping the columns
+/// still occupied by the cells of previous rows. `spans` holds the number of
+/// rows each column remains occupied for, and is updated for the next row.
+fn place_cells<'a>(row_elem: ElementRef<'a>, spans: &mut Vec<u32>) -> Vec<(usize, ElementRef<'a>)> {
+    let mut column = 0;
+    let mut cells = Vec::new();
+    for cell_elem in row_elem
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|elem| elem.value().name() == "td")
+    {
+        while spans.get(column).is_some_and(|&span| span > 0) {
+            column += 1;
+        }
+        if column == spans.len() {
+            spans.push(0);
+        }
+        // Invalid spans are reported when parsing the cell.
+        spans[column] = cell_elem
+            .value()
+            .attr("rowspan")
+            .and_then(|span| span.parse().ok())
+            .unwrap_or(1);
+        cells.push((column, cell_elem));
+        column += 1;
+    }
+    for span in spans.iter_mut() {
+        *span = span.saturating_sub(1);
+    }
+    cells
+}
+
+/// Parses the number at the start of `text`, ignoring any trailing
+/// characters (e.g. the ordinal indicator of "4º").
+fn parse_leading_number<T: FromStr>(text: &str) -> Option<T> {
+    let text = text.trim_start();
+    let end = text
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
+
+/// Parses a subject formatted as `code-name, grp.group`, possibly
+/// padded by whitespace.
+fn parse_subject(text: &str) -> Result<Subject, ParseError> {
+    let (code, rest) = text
+        .trim()
+        .split_once('-')
+        .ok_or(ParseErrorKind::InvalidSubjectFormat)?;
+    let (name, group) = rest
+        .rsplit_once(", grp.")
+        .ok_or(ParseErrorKind::InvalidSubjectFormat)?;
+    let code = code
+        .parse()
+        .map_err(|err| ParseErrorKind::InvalidSubjectCode(code.to_string(), err))?;
+    let group = group
+        .parse()
+        .map_err(|err| ParseErrorKind::InvalidSubjectGroup(group.to_string(), err))?;
+    Ok(Subject::new(code, name.trim(), group))
+}
+
+/// Parses a time range formatted as `HH:MM a HH:MM` (or `HH:MM to HH:MM`
+/// in English), where the start precedes the end.
+fn parse_time_range(text: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = text.split_once(" a ").or_else(|| text.split_once(" to "))?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+    (start < end).then_some((start, end))
+}
+
+struct Cell<'a> {
+    parser: &'a Parser<'a>,
+    row_time: NaiveTime,
+    start_time: NaiveTime,
+    duration: Duration,
+    column: usize,
+    header: Option<&'a str>,
+    weekday: Weekday,
+    kind: Option<SessionKind>,
+    group_elem: ElementRef<'a>,
+}
+
+impl<'a> Cell<'a> {
+    fn new(
+        parser: &'a Parser<'a>,
+        row_time: NaiveTime,
+        slot: Duration,
+        column: usize,
+        header: Option<&'a str>,
+        elem: ElementRef<'a>,
+    ) -> Result<Self, ParseError> {
+        let locate = |err: ParseError| {
+            err.at_time(row_time)
+                .at_column(column, header)
+                .near(&elem.text().collect::<String>())
+        };
+        let mut start_time = row_time;
+        let mut duration = slot
+            * elem
+                .value()
+                .attr("rowspan")
+                .map_or(Ok(1), |span| {
+                    span.parse()
+                        .map_err(|err| ParseErrorKind::InvalidRowSpan(span.to_string(), err))
+                })
+                .map_err(|err| locate(err.into()))?;
+
+        // Some cells start with an unclassed element containing the session kind.
+        let kind = elem
+            .children()
+            .find_map(ElementRef::wrap)
+            .filter(|label_elem| {
+                label_elem.value().name() == "div" && label_elem.value().attr("class").is_none()
+            })
+            .map(|label_elem| SessionKind::from_label(&label_elem.text().collect::<String>()));
+
+        let group_elem = elem
+            .select(&GROUP_SELECTOR)
+            .next()
+            .ok_or_else(|| locate(ParseErrorKind::MissingGroupElem.into()))?;
+
+        // The time range shown in the cell (e.g. "15:00 a 17:00") takes
+        // precedence over its position in the grid, which may be clipped.
+        let time_label = group_elem
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|label_elem| label_elem.value().name() == "div")
+            .find_map(|label_elem| {
+                let label = label_elem.text().collect::<String>();
+                parse_time_range(&label).map(|range| (label, range))
+            });
+        if let Some((label, (start, end))) = time_label {
+            let grid_end = start_time + duration;
+            if start != start_time || end != grid_end {
+                let label = label.trim();
+                let err = ParseErrorKind::MismatchedTimeLabel(label.into(), start_time, grid_end);
+                parser.note(
+                    ReportedItem::Cell,
+                    locate(ParseError::from(err).near(label)),
+                );
+            }
+            start_time = start;
+            duration = end - start;
+        }
+
+        // The sessions of the cell take place on the weekday of its column.
+        let weekday = header
+            .and_then(|header| locale::weekday(parser.language, header))
+            .ok_or_else(|| {
+                let err = ParseErrorKind::UnknownWeekday(header.map(str::to_string));
+                locate(err.into())
+            })?;
+
+        Ok(Self {
+            parser,
+            row_time,
+            start_time,
+            duration,
+            column,
+            header,
+            weekday,
+            kind,
+            group_elem,
+        })
+    }
+
+    /// Adds the location of the cell to the context of `err`.
+    fn locate(&self, err: ParseError) -> ParseError {
+        let err = err
+            .at_time(self.row_time)
+            .at_column(self.column, self.header);
+        match self.group_elem.text().next() {
+            Some(subject) => err.in_subject(subject),
+            None => err,
+        }
+    }
+
+    fn push_sessions(
+        &self,
+        dest: &mut Vec<Session>,
+        kinds: &mut HashMap<Subject, SessionKind>,
+    ) -> Result<(), ParseError> {
+        let subject_text = self
+            .group_elem
+            .first_child()
+            .ok_or(ParseErrorKind::ChildlessGroupElem)
+            .and_then(|node| {
+                node.value()
+                    .as_text()
+                    .ok_or(ParseErrorKind::NonTextualGroupChild)
+            })
+            .map_err(|err| self.locate(err.into()))?;
+        let subject = parse_subject(subject_text).map_err(|err| self.locate(err))?;
+
+        // Unlabelled cells share the kind of the subject's previous cells.
+        let kind = match &self.kind {
+            Some(kind) => {
+                kinds.insert(subject.clone(), kind.clone());
+                Some(kind)
+            }
+            None => kinds.get(&subject),
+        };
+
+        let session_elems = self
+            .group_elem
+            .select(&SESSION_SELECTOR)
+            .next()
+            .ok_or_else(|| self.locate(ParseErrorKind::MissingSessionsElem.into()))?
+            .children();
+
+        let ranges = self.parser.collect(
+            session_elems
+                .tuples()
+                .map(|(date_range_span, location_span, _)| {
+                    let date_range_span = ElementRef::wrap(date_range_span)
+                        .ok_or(ParseErrorKind::NonElementSessionDateNode)?;
+                    let location_span = ElementRef::wrap(location_span)
+                        .ok_or(ParseErrorKind::NonElementSessionLocationNode)?;
+                    self.parse_session(date_range_span, location_span)
+                })
+                .map(|result| result.map_err(|err| self.locate(err))),
+            ReportedItem::Session,
+        )?;
+
+        // Group the date ranges by room, keeping their order.
+        let mut rooms: Vec<(&str, Vec<DateRange>)> = Vec::new();
+        for (range, room) in ranges {
+            match rooms.iter_mut().find(|(other, _)| *other == room) {
+                Some((_, dates)) => dates.push(range),
+                None => rooms.push((room, vec![range])),
+            }
+        }
+        dest.extend(rooms.into_iter().map(|(room, dates)| {
+            Session::new(
+                subject.clone(),
+                kind.cloned(),
+                self.weekday,
+                self.start_time,
+                self.duration,
+                dates,
+                room,
+            )
+        }));
+        Ok(())
+    }
+
+    fn parse_session(
+        &self,
+        date_range_span: ElementRef,
+        location_span: ElementRef<'a>,
+    ) -> Result<(DateRange, &'a str), ParseError> {
+        let raw_range = date_range_span
+            .first_child()
+            .ok_or(ParseErrorKind::MissingDateRange)?
+            .value()
+            .as_text()
+            .ok_or(ParseErrorKind::NonTextualDateRange)?
+            .trim_end_matches(':');
+        let range = self
+            .parse_date_range(raw_range)
+            .map_err(|err| err.near(raw_range))?;
+        for date in [range.start(), range.end()] {
+            if date.weekday() != self.weekday {
+                let err = ParseError::from(ParseErrorKind::MismatchedWeekday(date, self.weekday));
+                self.parser
+                    .warn(ReportedItem::Session, self.locate(err.near(raw_range)))?;
+                break;
+            }
+        }
+        let location = location_span
+            .first_child()
+            .ok_or_else(|| ParseError::from(ParseErrorKind::MissingLocationSpan).near(raw_range))?
+            .value()
+            .as_text()
+            .ok_or_else(|| {
+                ParseError::from(ParseErrorKind::NonTextualLocationSpan).near(raw_range)
+            })?;
+        Ok((range, location))
+    }
+
+    fn parse_date_range(&self, range: &str) -> Result<DateRange, ParseError> {
+        // If the string doesn't contain a dash, return an empty single-day range.
+        match range.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (self.parse_date(start)?, self.parse_date(end)?);
+                if end < start {
+                    return Err(ParseErrorKind::ReversedDateRange.into());
+                }
+                Ok(DateRange::new(start, end))
+            }
+            None => Ok(DateRange::single(self.parse_date(range)?)),
+        }
+    }
+
+    fn parse_date(&self, date: &str) -> Result<NaiveDate, ParseError> {
+        let (day, month) = date
+            .split_once('.')
+            .ok_or_else(|| ParseErrorKind::InvalidDateFormat(date.to_string()))?;
+        let day = day
+            .parse()
+            .map_err(|err| ParseErrorKind::InvalidDay(day.to_string(), err))?;
+        let month = locale::month(self.parser.language, month)
+            .ok_or_else(|| ParseErrorKind::InvalidMonth(month.to_string()))?;
+
+        // Dates don't include the year, infer it from the academic year.
+        let year = self.time_table_id().calendar_year(month);
+        NaiveDate::from_ymd_opt(year, month, day)
+            .ok_or_else(|| ParseErrorKind::InvalidDate(day, month).into())
+    }
+
+    const fn time_table_id(&self) -> &TimetableId {
+        self.parser.time_table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Subject;
+    use crate::parse::{
+        grid_step, parse_leading_number, parse_subject, parse_time_range, place_cells,
+    };
+    use chrono::{Duration, NaiveTime};
+    use scraper::{ElementRef, Html, Selector};
+
+    #[test]
+    fn subject_fields() {
+        let subject =
+            parse_subject("18281-ANÁLISIS FUNCIONAL APLICADO, grp.121\u{a0}\u{a0}").unwrap();
+        assert_eq!(
+            subject,
+            Subject::new(18281, "ANÁLISIS FUNCIONAL APLICADO", 121)
+        );
+
+        // Only the last group designator delimits the subject name.
+        let subject = parse_subject("13870-SISTEMAS, grp.A, grp.89").unwrap();
+        assert_eq!(subject, Subject::new(13870, "SISTEMAS, grp.A", 89));
+
+        assert!(parse_subject("ANÁLISIS FUNCIONAL APLICADO").is_err());
+        assert!(parse_subject("18281-ANÁLISIS FUNCIONAL APLICADO").is_err());
+        assert!(parse_subject("AFA-ANÁLISIS FUNCIONAL APLICADO, grp.121").is_err());
+    }
+
+    #[test]
+    fn leading_number() {
+        assert_eq!(parse_leading_number("1er. cuatrimestre"), Some(1));
+        assert_eq!(parse_leading_number(" 4º curso"), Some(4u8));
+        assert_eq!(parse_leading_number::<u8>("curso"), None);
+    }
+
+    #[test]
+    fn time_range() {
+        let time = |hour, minutes| NaiveTime::from_hms_opt(hour, minutes, 0).unwrap();
+        assert_eq!(
+            parse_time_range("15:00 a 17:00"),
+            Some((time(15, 0), time(17, 0)))
+        );
+        assert_eq!(
+            parse_time_range(" 09:30 to 11:00 "),
+            Some((time(9, 30), time(11, 0)))
+        );
+        assert_eq!(parse_time_range("17:00 a 15:00"), None);
+        assert_eq!(parse_time_range("MAGISTRAL"), None);
+    }
+
+    #[test]
+    fn time_grid_step() {
+        let times = [(9, 0), (9, 30), (10, 0), (11, 0)]
+            .map(|(hour, minutes)| NaiveTime::from_hms_opt(hour, minutes, 0).unwrap());
+        let step = |times: &[Option<&NaiveTime>]| grid_step(times).map_err(|err| err.to_string());
+        assert_eq!(
+            step(&[Some(&times[0]), Some(&times[1]), Some(&times[2])]),
+            Ok(Duration::minutes(30))
+        );
+        // Unparsed rows are ignored.
+        assert_eq!(
+            step(&[Some(&times[0]), None, Some(&times[2]), Some(&times[3])]),
+            Ok(Duration::hours(1))
+        );
+        assert_eq!(step(&[Some(&times[0])]), Ok(Duration::minutes(15)));
+        assert_eq!(
+            step(&[Some(&times[0]), Some(&times[1]), Some(&times[2]), Some(&times[3])]),
+            Err("time grid rows are 30 minutes apart, but this row starts 60 minutes after the previous one (row 11:00)".into())
+        );
+    }
+
+    #[test]
+    fn cell_columns() {
+        let html = Html::parse_fragment(
+            "<table><tbody>\
+            <tr><th>9</th><td rowspan=\"2\">A</td><td>B</td><td rowspan=\"3\">C</td></tr>\
+            <tr><th>10</th><td>D</td></tr>\
+            <tr><th>11</th><td>E</td><td>F</td></tr>\
+            </tbody></table>",
+        );
+        let selector = Selector::parse("tr").unwrap();
+        let mut spans = Vec::new();
+        let columns: Vec<Vec<(usize, String)>> = html
+            .select(&selector)
+            .map(|row| {
+                place_cells(row, &mut spans)
+                    .into_iter()
+                    .map(|(column, elem): (usize, ElementRef)| (column, elem.inner_html()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                vec![(0, "A".into()), (1, "B".into()), (2, "C".into())],
+                vec![(1, "D".into())],
+                vec![(0, "E".into()), (1, "F".into())],
+            ]
+        );
+    }
+
+    #[test]
+    fn spanned_cell_columns() {
+        // Cells skip any column occupied by a previous row, even between
+        // free columns, and invalid spans occupy a single row.
+        let html = Html::parse_fragment(
+            "<table><tbody>\
+            <tr><th>9</th><td>A</td><td rowspan=\"3\">B</td><td rowspan=\"x\">C</td></tr>\
+            <tr><th>10</th><td>D</td><td>E</td><td>F</td></tr>\
+            <tr><th>11</th><td rowspan=\"2\">G</td><td>H</td></tr>\
+            <tr><th>12</th><td>I</td><td>J</td></tr>\
+            </tbody></table>",
+        );
+        let selector = Selector::parse("tr").unwrap();
+        let mut spans = Vec::new();
+        let columns: Vec<Vec<(usize, String)>> = html
+            .select(&selector)
+            .map(|row| {
+                place_cells(row, &mut spans)
+                    .into_iter()
+                    .map(|(column, elem): (usize, ElementRef)| (column, elem.inner_html()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                vec![(0, "A".into()), (1, "B".into()), (2, "C".into())],
+                vec![(0, "D".into()), (2, "E".into()), (3, "F".into())],
+                vec![(0, "G".into()), (2, "H".into())],
+                vec![(1, "I".into()), (2, "J".into())],
+            ]
+        );
+        assert_eq!(spans, vec![0, 0, 0, 0]);
+    }
+}
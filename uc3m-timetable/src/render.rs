@@ -0,0 +1,302 @@
+use crate::ical::components::{Alarm, Event, EventStatus, Recurrence, TimeUnit, TimeZone, Trigger};
+use crate::ical::{Calendar, Component, Method};
+use crate::model::{DateRange, Session, Subject};
+use crate::util::fnv1a;
+use crate::Timetable;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone as _};
+use chrono_tz::Tz;
+
+static PRODUCT_NAME: &str = "uc3m-timetable.hugmanrique.me";
+static SPEC_VERSION: &str = "2.0";
+
+/// Renders a timetable as an iCalendar object, containing
+/// the events of every session and the definition of their
+/// time zone.
+pub fn calendar(timetable: &Timetable) -> Calendar {
+    let events = timetable
+        .sessions()
+        .iter()
+        .flat_map(|session| events(timetable, session))
+        .map(Component::from);
+    let global_time_zones = timetable.options.global_time_zones;
+    let components = (!global_time_zones)
+        .then(|| time_zone(timetable).into())
+        .into_iter()
+        .chain(events)
+        .collect();
+    let metadata = timetable.metadata();
+    let options = &timetable.options;
+    let mut calendar = Calendar::new(PRODUCT_NAME, SPEC_VERSION, components)
+        .method(Method::Publish)
+        .name(&metadata.name())
+        .description(&metadata.to_string())
+        .time_zone(timetable.id().time_zone);
+    if let Some(interval) = options.refresh_interval {
+        calendar = calendar.refresh_interval(interval);
+    }
+    if let Some(source) = &options.source {
+        calendar = calendar.source(source);
+    }
+    if let Some(color) = &options.color {
+        calendar = calendar.color(color);
+    }
+    if global_time_zones {
+        calendar.global_time_zones()
+    } else {
+        calendar
+    }
+}
+
+/// Defines the time zone of the timetable, from the start of
+/// its first session day to the end of its last session day.
+fn time_zone(timetable: &Timetable) -> TimeZone {
+    let time_zone = &timetable.id().time_zone;
+    let dates = timetable.sessions().iter().flat_map(Session::dates);
+    let first = dates.clone().map(DateRange::start).min().unwrap();
+    let last = dates.map(DateRange::end).max().unwrap();
+    let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+    TimeZone::new(
+        *time_zone,
+        localize(time_zone, first, midnight),
+        localize(time_zone, last.succ_opt().unwrap(), midnight),
+    )
+}
+
+/// Creates the event of a session, whose occurrences are expressed as a
+/// single series. A weekly rule, with the largest interval that fits the
+/// occurrences, covers the occurrences up to the point that needs the fewest
+/// exclusions and extra dates: the dates of the rule without a session are
+/// excluded, and the remaining occurrences are added. The occurrences dropped
+/// on holidays are kept in the series, and overridden by cancelled events.
+fn events(timetable: &Timetable, session: &Session) -> Vec<Event> {
+    let time_zone = &timetable.id().time_zone;
+    let subject = session.subject();
+    let uid = format!("{:016x}@{}", uid_hash(timetable, session), PRODUCT_NAME);
+    let summary = match session.kind() {
+        Some(kind) if timetable.options.kind_in_summary => {
+            format!("[{}] {}", kind, subject.name())
+        }
+        _ => subject.name().to_string(),
+    };
+    let description = format!("Course {}, group {}", subject.code(), subject.group());
+
+    let mut occurrences: Vec<_> = session.occurrences().collect();
+    occurrences.sort_unstable();
+    occurrences.dedup();
+    let first = occurrences[0];
+    let start = localize(time_zone, first, session.start_time());
+
+    let mut event = Event::new(&uid, *timetable.created_on(), start)
+        .summary(&summary)
+        .description(description)
+        .location(session.room())
+        .duration(session.duration());
+    // The SEQUENCE property is left unset, since a single timetable
+    // doesn't know which sessions were rescheduled.
+    event = event
+        .category(subject.name())
+        .url(timetable.id().url().as_str())
+        .color(subject_color(subject));
+    if let Some(kind) = session.kind() {
+        event = event.category(kind.name());
+    }
+    if let Some((latitude, longitude)) = campus_location(timetable.metadata().campus()) {
+        event = event.geo(latitude, longitude);
+    }
+    for &before in &timetable.options.reminders {
+        event = event.alarm(Alarm::display(Trigger::Relative(-before), &summary));
+    }
+
+    let (interval, until) = weekly_rule(&occurrences);
+    let on_rule = |date: NaiveDate| {
+        let days = (date - first).num_days();
+        date <= until && days % (7 * interval) == 0
+    };
+    if until > first {
+        let mut rule = Recurrence::until(
+            TimeUnit::Week,
+            localize(time_zone, until, session.start_time()),
+        );
+        if interval > 1 {
+            rule.interval(interval as u32);
+        }
+        event = event.recurrence(rule);
+    }
+    for &date in occurrences.iter().filter(|&&date| !on_rule(date)) {
+        event = event.include(localize(time_zone, date, session.start_time()));
+    }
+    let skipped = (0..)
+        .map(|period| first + Duration::weeks(period * interval))
+        .take_while(|&date| date <= until)
+        .filter(|date| occurrences.binary_search(date).is_err());
+    for date in skipped {
+        event = event.exclude(localize(time_zone, date, session.start_time()));
+    }
+
+    let cancelled = session.excluded_dates().iter().map(|&date| {
+        let start = localize(time_zone, date, session.start_time());
+        Event::new(&uid, *timetable.created_on(), start)
+            .recurrence_id(start)
+            .status(EventStatus::Cancelled)
+            .summary(&summary)
+            .duration(session.duration())
+    });
+    std::iter::once(event).chain(cancelled).collect()
+}
+
+/// Returns the interval in weeks and the last date of the weekly rule that
+/// starts on the first of the given occurrences, which must be sorted.
+/// The rule covers at least three occurrences, or ends on the first one.
+fn weekly_rule(occurrences: &[NaiveDate]) -> (i64, NaiveDate) {
+    let first = occurrences[0];
+    let weeks: Vec<_> = occurrences
+        .iter()
+        .map(|&date| date - first)
+        .filter(|days| days.num_days() % 7 == 0)
+        .map(|days| days.num_weeks())
+        .collect();
+    let interval = weeks.iter().copied().fold(0, gcd).max(1);
+    // Ending the rule at the k-th occurrence on the rule excludes the
+    // periods without an occurrence until then, and adds the later
+    // occurrences outside of the rule.
+    let (last, _) = weeks
+        .iter()
+        .enumerate()
+        .min_by_key(|&(index, &week)| {
+            let periods = week / interval + 1;
+            let excluded = periods - index as i64 - 1;
+            let added = (weeks.len() - index - 1) as i64;
+            // Prefer longer rules if the number of dates is the same.
+            (excluded + added, -(index as i64))
+        })
+        .unwrap();
+    if last < 2 {
+        // Two dates don't make a series (such as a 4-weekly one),
+        // so they are added as extra dates instead.
+        return (1, first);
+    }
+    (interval, first + Duration::weeks(weeks[last]))
+}
+
+/// Returns the greatest common divisor of two numbers.
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Returns the color used to display the sessions of a subject,
+/// which is chosen from a palette by the subject code.
+fn subject_color(subject: &Subject) -> &'static str {
+    static PALETTE: [&str; 12] = [
+        "steelblue",
+        "darkorange",
+        "seagreen",
+        "firebrick",
+        "mediumpurple",
+        "sienna",
+        "orchid",
+        "slategray",
+        "olivedrab",
+        "darkcyan",
+        "goldenrod",
+        "royalblue",
+    ];
+    let hash = fnv1a(&subject.code().to_le_bytes());
+    PALETTE[(hash % PALETTE.len() as u64) as usize]
+}
+
+/// Returns the latitude and longitude of a UC3M campus, in degrees.
+fn campus_location(campus: &str) -> Option<(f64, f64)> {
+    let campus = campus.to_lowercase();
+    if campus.contains("legan") {
+        Some((40.332_472, -3.765_167))
+    } else if campus.contains("getafe") {
+        Some((40.316_389, -3.726_111))
+    } else if campus.contains("colmenarejo") {
+        Some((40.544_722, -4.012_222))
+    } else if campus.contains("toledo") {
+        Some((40.406_944, -3.711_389))
+    } else {
+        None
+    }
+}
+
+/// Hashes the fields that identify the event, so that its UID remains stable
+/// when the timetable is regenerated and the event details change. The dates
+/// are left out, so that adding or dropping a date updates the event in place;
+/// the room tells apart the sessions of a cell that take place in several rooms.
+fn uid_hash(timetable: &Timetable, session: &Session) -> u64 {
+    let id = timetable.id();
+    let subject = session.subject();
+    let key = format!(
+        "{}/{}/{}/{}/{}/{}/{}/{}/{}/{}/{}",
+        id.year,
+        id.plan,
+        id.center,
+        id.grade,
+        id.group,
+        id.period,
+        subject.code(),
+        subject.group(),
+        session.weekday(),
+        session.start_time().format("%H:%M"),
+        session.room(),
+    );
+    fnv1a(key.as_bytes())
+}
+
+/// Returns the instant at which the local `date` and `time` occur.
+fn localize(time_zone: &Tz, date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+    let local = date.and_time(time);
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .unwrap_or_else(|| {
+            // The local time falls within a daylight saving gap,
+            // move it past the transition (usually an hour later).
+            time_zone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+                .expect("local time is skipped by multiple transitions")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::weekly_rule;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn weekly_rules() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2022, month, day).unwrap();
+
+        // Weekly sessions with a holiday gap.
+        let dates = [date(9, 5), date(9, 12), date(9, 26), date(10, 3)];
+        assert_eq!(weekly_rule(&dates), (1, date(10, 3)));
+
+        // Biweekly labs.
+        let dates = [date(9, 5), date(9, 19), date(10, 3), date(10, 31)];
+        assert_eq!(weekly_rule(&dates), (2, date(10, 31)));
+
+        // One-off sessions after the weekly ones, and on another weekday.
+        let dates = [
+            date(9, 5),
+            date(9, 12),
+            date(9, 19),
+            date(9, 21),
+            date(12, 19),
+        ];
+        assert_eq!(weekly_rule(&dates), (1, date(9, 19)));
+
+        // Two sessions four weeks apart, and a third one on another weekday.
+        let dates = [date(11, 3), date(12, 1)];
+        assert_eq!(weekly_rule(&dates), (1, date(11, 3)));
+        let dates = [date(11, 3), date(11, 10), date(11, 16)];
+        assert_eq!(weekly_rule(&dates), (1, date(11, 3)));
+
+        assert_eq!(weekly_rule(&[date(9, 5)]), (1, date(9, 5)));
+    }
+}
@@ -51,9 +51,65 @@ where
     (adapter, state)
 }
 
+pub type ProcessErrors<E> = Rc<RefCell<Vec<E>>>;
+
+/// An [`Iterator`] adapter that yields `Some(item)` elements for each
+/// `Some(Ok(item))` element returned by the wrapped iterator. Every
+/// `Some(Err(_))` element is skipped, and the errors are gathered in
+/// the vector that can be accessed by borrowing [`Self::errors`].
+pub struct ProcessAll<I, E> {
+    iter: I,
+    errors: ProcessErrors<E>,
+}
+
+impl<T, E, I> Iterator for ProcessAll<I, E>
+where
+    I: Iterator<Item = Result<T, E>>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for result in &mut self.iter {
+            match result {
+                Ok(item) => return Some(item),
+                Err(e) => self.errors.borrow_mut().push(e),
+            }
+        }
+        None
+    }
+}
+
+/// Returns an [`Iterator`] that yields the `item` of each `Ok(item)`
+/// element returned by the wrapped iterator. The errors of the
+/// `Err(err)` elements are skipped and stored in order in the
+/// mutable vector.
+#[must_use]
+pub fn process_all<T, E, I>(iter: I) -> (ProcessAll<I::IntoIter, E>, ProcessErrors<E>)
+where
+    I: IntoIterator<Item = Result<T, E>>,
+{
+    let iter = iter.into_iter();
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let adapter = ProcessAll {
+        iter,
+        errors: errors.clone(),
+    };
+    (adapter, errors)
+}
+
+/// Computes the 64-bit FNV-1a hash of `bytes`. Unlike the hashers of the
+/// standard library, the result is stable across releases and platforms.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::util::process;
+    use crate::util::{fnv1a, process, process_all};
 
     #[derive(Debug, Eq, PartialEq, Copy, Clone)]
     struct DummyError {}
@@ -88,4 +144,31 @@ mod tests {
         assert_eq!(iter.next(), None);
         assert_eq!(*result.borrow(), Err(DummyError {}));
     }
+
+    #[test]
+    fn all_errs() {
+        let (iter, errors) = process_all(vec![
+            Ok(1),
+            Err(DummyError {}),
+            Ok(2),
+            Err(DummyError {}),
+            Ok(3),
+        ]);
+        assert_eq!(iter.collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(*errors.borrow(), vec![DummyError {}, DummyError {}]);
+    }
+
+    #[test]
+    fn all_errs_none() {
+        let (mut iter, errors) = process_all(Vec::<Result<u8, DummyError>>::new());
+        assert_eq!(iter.next(), None);
+        assert!(errors.borrow().is_empty());
+    }
+
+    #[test]
+    fn fnv1a_hash() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+    }
 }
@@ -1,16 +1,579 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Weekday};
 use scraper::Html;
 use std::fs;
-use uc3m_timetable::ical::format_date_time;
-use uc3m_timetable::{Result, Timetable, TimetableId, UC3M_TIMEZONE};
+use uc3m_timetable::clock::{ContentStamp, FixedClock};
+use uc3m_timetable::holidays::HolidayCalendar;
+use uc3m_timetable::ical::{Calendar, ComponentHolder, PropHolder};
+use uc3m_timetable::model::{DateRange, Session, SessionKind, Subject, TimetableMetadata};
+use uc3m_timetable::{
+    Language, ParseError, ParseErrorKind, ReportedItem, Result, Severity, Timetable, TimetableId,
+    TimetableOptions, UC3M_TIMEZONE,
+};
+
+fn render(id: TimetableId, html_path: &str) -> Result<String> {
+    let html = Html::parse_document(&fs::read_to_string(html_path)?);
+    let clock = FixedClock(
+        DateTime::parse_from_rfc3339("2022-08-19T10:00:00Z")
+            .unwrap()
+            .into(),
+    );
+    let options = TimetableOptions::new().clock(clock);
+    let timetable = Timetable::parse_with_options(id, &html, &options)?;
+    Ok(timetable.calendar().to_string())
+}
+
+fn assert_parses(id: TimetableId, html_path: &str, expected_path: &str) -> Result<()> {
+    let expected = fs::read_to_string(expected_path)?;
+    assert_eq!(render(id, html_path)?, expected);
+    Ok(())
+}
 
 #[tokio::test]
 async fn parse_timetable() -> Result<()> {
     let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    assert_parses(id, "tests/timetable.html", "tests/expected.ics")?;
+
+    // The calendar is only rendered once.
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable.html")?);
+    let timetable = Timetable::parse(id, &html)?;
+    assert!(std::ptr::eq(timetable.calendar(), timetable.calendar()));
+    let calendar = timetable.calendar().to_string();
+    assert_eq!(timetable.into_calendar().to_string(), calendar);
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_second_semester() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 2, UC3M_TIMEZONE);
+    assert_parses(
+        id,
+        "tests/timetable_second_semester.html",
+        "tests/expected_second_semester.ics",
+    )
+}
+
+#[tokio::test]
+async fn parse_december_january() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    assert_parses(
+        id,
+        "tests/timetable_december_january.html",
+        "tests/expected_december_january.ics",
+    )
+}
+
+#[tokio::test]
+async fn reject_reversed_date_range() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = fs::read_to_string("tests/timetable_december_january.html")?
+        .replace("12.dic-16.ene", "16.ene-12.dic");
+    let html = Html::parse_document(&html);
+    assert!(Timetable::parse(id, &html).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn kind_in_summary() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 2, UC3M_TIMEZONE);
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable_second_semester.html")?);
+    let options = TimetableOptions::new().kind_in_summary(true);
+    let timetable = Timetable::parse_with_options(id, &html, &options)?;
+
+    // The unlabelled Wednesday cell inherits the kind of the Monday cell.
+    let calendar = timetable.calendar().to_string();
+    assert_eq!(
+        calendar
+            .matches("SUMMARY:[Lecture] TEORÍA DE JUEGOS")
+            .count(),
+        2
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_metadata() -> Result<()> {
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable.html")?);
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let timetable = Timetable::parse(id, &html)?;
+    assert_eq!(
+        timetable.metadata(),
+        &TimetableMetadata::new(
+            2022,
+            1,
+            "Escuela Politécnica Superior",
+            "Leganés",
+            "Grado en Matemática Aplicada y Computación",
+            4,
+            121
+        )
+    );
+
+    // The page belongs to another group
+    let id = TimetableId::new(2022, 433, 2, 4, 122, 1, UC3M_TIMEZONE);
+    assert!(Timetable::parse(id, &html).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_headerless_timetable() -> Result<()> {
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable_headerless.html")?);
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let err = Timetable::parse(id, &html).unwrap_err();
+    let err = err.downcast_ref::<ParseError>().unwrap();
+    assert_eq!(err.kind(), &ParseErrorKind::MissingHeaderElem);
+
+    // In lenient mode, the metadata is derived from the identifier.
+    let options = TimetableOptions::new().lenient(true);
+    let timetable = Timetable::parse_with_options(id, &html, &options)?;
+    assert_eq!(
+        timetable.metadata(),
+        &TimetableMetadata::new(2022, 1, "Center 2", "", "Plan 433", 4, 121)
+    );
+    assert_eq!(
+        timetable.metadata().to_string(),
+        "Plan 433, year 4, group 121 (2022/2023, semester 1, Center 2)"
+    );
+    let diagnostics = timetable.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].item(), ReportedItem::Metadata);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "skipped metadata: cannot find the `#nombreApp` header element"
+    );
+    assert_eq!(timetable.sessions().len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_sessions() -> Result<()> {
     let html = Html::parse_document(&fs::read_to_string("tests/timetable.html")?);
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
     let timetable = Timetable::parse(id, &html)?;
 
-    let expected = fs::read_to_string("tests/expected.ics")?
-        .replace("{DTSTAMP}", &format_date_time(timetable.created_on()));
-    assert_eq!(timetable.calendar().to_string(), expected);
+    let sessions = timetable.sessions();
+    assert_eq!(sessions.len(), 7);
+    let date = |month, day| NaiveDate::from_ymd_opt(2022, month, day).unwrap();
+    assert_eq!(
+        sessions[0],
+        Session::new(
+            Subject::new(18281, "ANÁLISIS FUNCIONAL APLICADO", 121),
+            Some(SessionKind::Lecture),
+            Weekday::Mon,
+            NaiveTime::from_hms_opt(15, 0, 0).unwrap(),
+            Duration::hours(2),
+            vec![
+                DateRange::new(date(9, 5), date(10, 24)),
+                DateRange::new(date(11, 7), date(12, 12))
+            ],
+            "Aula 7.1.J02"
+        )
+    );
+    assert_eq!(sessions[6].weekday(), Weekday::Thu);
+    assert_eq!(sessions[6].dates()[1], DateRange::single(date(12, 1)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn lenient_parsing() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = fs::read_to_string("tests/timetable.html")?
+        .replacen("05.sep-24.oct", "05.sept-24.oct", 1) // invalid month
+        .replacen("18283-PROGRAMACIÓN FUNCIONAL", "PROGRAMACIÓN FUNCIONAL", 1); // missing code
+    let html = Html::parse_document(&html);
+    assert!(Timetable::parse(id, &html).is_err());
+
+    let options = TimetableOptions::new().lenient(true);
+    let timetable = Timetable::parse_with_options(id, &html, &options)?;
+    let diagnostics = timetable.diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].item(), ReportedItem::Session);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "skipped session: invalid month value `sept` (row 15:00, column 0 (LUNES), \
+         subject `18281-ANÁLISIS FUNCIONAL APLICADO, grp.121`, near `05.sept-24.oct`)"
+    );
+    assert_eq!(diagnostics[1].item(), ReportedItem::Cell);
+    let context = diagnostics[1].error().context();
+    assert_eq!(context.time(), NaiveTime::from_hms_opt(17, 0, 0));
+    assert_eq!(context.header(), Some("LUNES"));
+    assert_eq!(
+        diagnostics[1].error().kind(),
+        &ParseErrorKind::InvalidSubjectFormat
+    );
+
+    // The remaining date range of the first cell is kept.
+    let sessions = timetable.sessions();
+    assert_eq!(sessions.len(), 6);
+    assert_eq!(sessions[0].dates().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_english_timetable() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    assert_parses(
+        id,
+        "tests/timetable_english.html",
+        "tests/expected_english.ics",
+    )?;
+
+    // The language may also be set explicitly, which only changes the URL
+    // of the events.
+    let unfold = |calendar: String| calendar.replace("\r\n ", "");
+    for (language, html_path, expected_path) in [
+        (
+            Language::English,
+            "tests/timetable_english.html",
+            "tests/expected_english.ics",
+        ),
+        (
+            Language::Spanish,
+            "tests/timetable_december_january.html",
+            "tests/expected_december_january.ics",
+        ),
+    ] {
+        let expected = unfold(fs::read_to_string(expected_path)?);
+        let calendar = unfold(render(id.language(language), html_path)?);
+        assert_eq!(calendar.lines().count(), expected.lines().count());
+        for (line, expected) in calendar.lines().zip(expected.lines()) {
+            if expected.starts_with("URL:") {
+                assert_eq!(line, format!("{}&lang={}", expected, language.code()));
+            } else {
+                assert_eq!(line, expected);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_mismatched_language() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE).language(Language::Spanish);
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable_english.html")?);
+    assert!(Timetable::parse(id, &html).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn mismatched_weekday() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    // The cell is in the Tuesday column, but the range starts on a Monday.
+    let html =
+        fs::read_to_string("tests/timetable.html")?.replacen("06.sep-04.oct", "05.sep-04.oct", 1);
+    let html = Html::parse_document(&html);
+    let err = Timetable::parse(id, &html).unwrap_err();
+    let err = err.downcast_ref::<ParseError>().unwrap();
+    assert_eq!(
+        err.kind(),
+        &ParseErrorKind::MismatchedWeekday(
+            NaiveDate::from_ymd_opt(2022, 9, 5).unwrap(),
+            Weekday::Tue
+        )
+    );
+    assert_eq!(err.context().header(), Some("MARTES"));
+
+    let options = TimetableOptions::new().lenient(true);
+    let timetable = Timetable::parse_with_options(id, &html, &options)?;
+    let diagnostics = timetable.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity(), Severity::Warning);
+    assert!(diagnostics[0]
+        .to_string()
+        .starts_with("kept session: date 05-09-2022 does not fall on the Tue of its column"));
+
+    // The session is kept on the weekday of its column.
+    let sessions = timetable.sessions();
+    assert_eq!(sessions.len(), 7);
+    assert_eq!(sessions[1].weekday(), Weekday::Tue);
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_weekday_header() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = fs::read_to_string("tests/timetable.html")?.replacen(">MARTES<", ">FESTIVO<", 1);
+    let html = Html::parse_document(&html);
+    let err = Timetable::parse(id, &html).unwrap_err();
+    let err = err.downcast_ref::<ParseError>().unwrap();
+    assert_eq!(
+        err.kind(),
+        &ParseErrorKind::UnknownWeekday(Some("FESTIVO".into()))
+    );
+
+    // The weekday isn't guessed from the dates of the cells.
+    let options = TimetableOptions::new().lenient(true);
+    let timetable = Timetable::parse_with_options(id, &html, &options)?;
+    let diagnostics = timetable.diagnostics();
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics.iter().all(|diagnostic| {
+        diagnostic.item() == ReportedItem::Cell
+            && diagnostic.severity() == Severity::Error
+            && diagnostic.error().context().header() == Some("FESTIVO")
+    }));
+    assert!(timetable
+        .sessions()
+        .iter()
+        .all(|session| session.weekday() != Weekday::Tue));
+    Ok(())
+}
+
+#[tokio::test]
+async fn time_label_precedence() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    // The first cell spans 6 slots, but its label reads "15:00 a 17:00".
+    let html =
+        fs::read_to_string("tests/timetable.html")?.replacen("rowspan=\"8\"", "rowspan=\"6\"", 1);
+    let html = Html::parse_document(&html);
+    let timetable = Timetable::parse(id, &html)?;
+    assert_eq!(timetable.sessions()[0].duration(), Duration::hours(2));
+
+    let diagnostics = timetable.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity(), Severity::Warning);
+    let time = |hour, minutes| NaiveTime::from_hms_opt(hour, minutes, 0).unwrap();
+    assert_eq!(
+        diagnostics[0].error().kind(),
+        &ParseErrorKind::MismatchedTimeLabel("15:00 a 17:00".into(), time(15, 0), time(16, 30))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_half_hour_grid() -> Result<()> {
+    // The cells have no time label, so their duration is given by their rowspan.
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    assert_parses(
+        id,
+        "tests/timetable_half_hour.html",
+        "tests/expected_december_january.ics",
+    )?;
+
+    let html = fs::read_to_string("tests/timetable_half_hour.html")?
+        .replace("12<sup>30</sup>", "12<sup>45</sup>");
+    let html = Html::parse_document(&html);
+    let err = Timetable::parse(id, &html).unwrap_err();
+    let err = err.downcast_ref::<ParseError>().unwrap();
+    assert_eq!(
+        err.kind(),
+        &ParseErrorKind::UnevenTimeGrid(Duration::minutes(30), Duration::minutes(45))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn stable_uids() -> Result<()> {
+    fn uids(calendar: &str) -> Vec<&str> {
+        calendar
+            .lines()
+            .filter(|line| line.starts_with("UID:"))
+            .collect()
+    }
+
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = fs::read_to_string("tests/timetable.html")?;
+    let calendar = Timetable::parse(id, &Html::parse_document(&html))?
+        .calendar()
+        .to_string();
+    let mut unique = uids(&calendar);
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), uids(&calendar).len());
+
+    // Renaming a subject or changing its padding keeps the event UIDs.
+    let html = html
+        .replace("ANÁLISIS FUNCIONAL APLICADO", "ANÁLISIS FUNCIONAL")
+        .replace(", grp.121&nbsp;&nbsp;", ", grp.121&nbsp;");
+    let renamed = Timetable::parse(id, &Html::parse_document(&html))?
+        .calendar()
+        .to_string();
+    assert_ne!(calendar, renamed);
+    assert_eq!(uids(&calendar), uids(&renamed));
+
+    // A cell that takes place in two rooms on the same dates.
+    let html = html.replacen(
+        "<span class=\"fechas\">07.nov-12.dic:</span><span class=\"aulas\">Aula 7.1.J02</span>",
+        "<span class=\"fechas\">05.sep-24.oct:</span><span class=\"aulas\">Aula 7.1.J03</span>",
+        1,
+    );
+    let split = Timetable::parse(id, &Html::parse_document(&html))?
+        .calendar()
+        .to_string();
+    assert!(split.contains("LOCATION:Aula 7.1.J03"));
+    let mut unique = uids(&split);
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), uids(&split).len());
+    assert_eq!(uids(&split).len(), uids(&calendar).len() + 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn exclude_holidays() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable.html")?);
+    let holidays = HolidayCalendar::from_csv("2022-10-13\n2022-11-03,Closed\n2022-12-01\n")?;
+    let options = TimetableOptions::new().holidays(holidays);
+    let timetable = Timetable::parse_with_options(id, &html, &options)?;
+
+    // The Thursday session at 19:00 only takes place on holidays.
+    let sessions = timetable.sessions();
+    assert_eq!(sessions.len(), 6);
+    let date = |month, day| NaiveDate::from_ymd_opt(2022, month, day).unwrap();
+    assert_eq!(sessions[4].weekday(), Weekday::Thu);
+    assert_eq!(
+        sessions[4].excluded_dates(),
+        &[date(10, 13), date(11, 3), date(12, 1)]
+    );
+
+    let calendar = timetable.calendar().to_string();
+    // The holidays stay in the series, and are cancelled by overrides.
+    assert!(calendar.contains("RRULE:FREQ=WEEKLY;UNTIL=20221201T160000Z\r\nEND:VEVENT\r\n"));
+    for date in ["20221013", "20221103", "20221201"] {
+        assert!(calendar.contains(&format!(
+            "STATUS:CANCELLED\r\n\
+            DURATION:PT2H\r\n\
+            RECURRENCE-ID;TZID=Europe/Madrid:{}T170000\r\n",
+            date
+        )));
+    }
+    assert_eq!(calendar.matches("STATUS:CANCELLED").count(), 3);
+    assert!(!calendar.contains("T190000"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn content_stamp() -> Result<()> {
+    fn parse(html: &str, now: &str, previous: Option<ContentStamp>) -> Result<Timetable> {
+        let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+        let mut options = TimetableOptions::new().clock(FixedClock(now.parse()?));
+        if let Some(stamp) = previous {
+            options = options.previous_stamp(stamp);
+        }
+        Timetable::parse_with_options(id, &Html::parse_document(html), &options)
+    }
+
+    let html = fs::read_to_string("tests/timetable.html")?;
+    let stamp = parse(&html, "2022-08-19T10:00:00Z", None)?.stamp();
+    assert_eq!(stamp.created_on().to_rfc3339(), "2022-08-19T10:00:00+00:00");
+
+    // The timestamp is kept while the contents don't change.
+    let timetable = parse(&html, "2022-08-20T10:00:00Z", Some(stamp))?;
+    assert_eq!(timetable.stamp(), stamp);
+
+    let changed = html.replace("7.1.J08", "7.1.J09");
+    let timetable = parse(&changed, "2022-08-20T10:00:00Z", Some(stamp))?;
+    assert_ne!(timetable.stamp().hash(), stamp.hash());
+    assert_eq!(
+        timetable.created_on().to_rfc3339(),
+        "2022-08-20T12:00:00+02:00"
+    );
+
+    // Timestamps from the future are never reused.
+    let timetable = parse(&html, "2022-08-18T10:00:00Z", Some(stamp))?;
+    assert_eq!(
+        timetable.stamp().created_on().to_rfc3339(),
+        "2022-08-18T10:00:00+00:00"
+    );
+
+    // Derived timestamps don't depend on the clock, and change with the contents.
+    fn derive(html: &str, now: &str) -> Result<Timetable> {
+        let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+        let options = TimetableOptions::new()
+            .clock(FixedClock(now.parse()?))
+            .content_stamp(true);
+        Timetable::parse_with_options(id, &Html::parse_document(html), &options)
+    }
+    let timetable = derive(&html, "2022-08-19T10:00:00Z")?;
+    let stamp = timetable.stamp();
+    assert_eq!(stamp.created_on().year(), 2021);
+    let calendar = timetable.into_calendar().to_string();
+    let timetable = derive(&html, "2022-08-20T10:00:00Z")?;
+    assert_eq!(timetable.stamp(), stamp);
+    assert_eq!(timetable.into_calendar().to_string(), calendar);
+    let timetable = derive(&changed, "2022-08-19T10:00:00Z")?;
+    assert_ne!(timetable.stamp().created_on(), stamp.created_on());
+    Ok(())
+}
+
+#[tokio::test]
+async fn global_time_zones() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable.html")?);
+    let calendar = Timetable::parse(id, &html)?.calendar().to_string();
+    assert_eq!(calendar.matches("BEGIN:VTIMEZONE").count(), 1);
+    assert!(!calendar.contains("TZID=/"));
+
+    let options = TimetableOptions::new().global_time_zones(true);
+    let calendar = Timetable::parse_with_options(id, &html, &options)?
+        .calendar()
+        .to_string();
+    assert!(!calendar.contains("BEGIN:VTIMEZONE"));
+    assert!(calendar.contains("DTSTART;TZID=/Europe/Madrid:20220905T150000\r\n"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn round_trip_calendar() -> Result<()> {
+    for path in [
+        "tests/expected.ics",
+        "tests/expected_second_semester.ics",
+        "tests/expected_december_january.ics",
+        "tests/expected_english.ics",
+    ] {
+        let expected = fs::read_to_string(path)?;
+        let calendar = expected.parse::<Calendar>().unwrap();
+        assert_eq!(calendar.to_string(), expected);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn session_reminders() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable.html")?);
+    let options = TimetableOptions::new()
+        .reminder(Duration::minutes(15))
+        .reminder(Duration::hours(1));
+    let calendar = Timetable::parse_with_options(id, &html, &options)?.into_calendar();
+    for event in calendar
+        .components()
+        .iter()
+        .filter(|component| component.name() == "VEVENT")
+    {
+        let triggers: Vec<_> = event
+            .components()
+            .iter()
+            .map(|alarm| alarm.first_prop("TRIGGER").unwrap().value())
+            .collect();
+        assert_eq!(triggers, ["-PT15M", "-PT1H"]);
+        assert_eq!(
+            event.components()[0]
+                .first_prop("DESCRIPTION")
+                .unwrap()
+                .value(),
+            event.first_prop("SUMMARY").unwrap().value()
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn calendar_props() -> Result<()> {
+    let id = TimetableId::new(2022, 433, 2, 4, 121, 1, UC3M_TIMEZONE);
+    let html = Html::parse_document(&fs::read_to_string("tests/timetable.html")?);
+    let options = TimetableOptions::new()
+        .source("https://uc3m-timetable.hugmanrique.me/?year=2022&plan=433")
+        .refresh_interval(Duration::days(1))
+        .color("teal");
+    let calendar = Timetable::parse_with_options(id, &html, &options)?.into_calendar();
+    let value = |name| calendar.first_prop(name).unwrap().value();
+    assert_eq!(value("METHOD"), "PUBLISH");
+    assert_eq!(value("X-WR-TIMEZONE"), "Europe/Madrid");
+    assert_eq!(value("REFRESH-INTERVAL"), "P1D");
+    assert_eq!(
+        value("SOURCE"),
+        "https://uc3m-timetable.hugmanrique.me/?year=2022&plan=433"
+    );
+    assert_eq!(value("COLOR"), "teal");
     Ok(())
 }